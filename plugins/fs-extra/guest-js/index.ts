@@ -3,6 +3,7 @@
 // SPDX-License-Identifier: MIT
 
 import { invoke } from "@tauri-apps/api/tauri";
+import { appWindow } from "@tauri-apps/api/window";
 
 export interface Permissions {
   /**
@@ -15,6 +16,21 @@ export interface Permissions {
   mode: number | undefined;
 }
 
+/**
+ * The error all commands of this plugin reject with when `Builder::structured_errors` is enabled.
+ * Otherwise they reject with the error message as a string.
+ */
+export interface FsExtraError {
+  kind:
+    | "NotFound"
+    | "PermissionDenied"
+    | "AlreadyExists"
+    | "NotADirectory"
+    | "SymlinkNotFollowed"
+    | "Other";
+  message: string;
+}
+
 /**
  * Metadata information about a file.
  * This structure is returned from the `metadata` function or method
@@ -89,6 +105,11 @@ export interface Metadata {
    * The number of blocks allocated to the file, in 512-byte units. Only available on Unix.
    */
   blocks: number | undefined;
+  /**
+   * A portable identity of the file, equal for paths referring to the same file, e.g. hard links.
+   * Use this rather than `dev` and `ino`, which are only available on Unix.
+   */
+  fileId: string | null;
 }
 
 interface BackendMetadata {
@@ -109,22 +130,842 @@ interface BackendMetadata {
   rdev: number | undefined;
   blksize: number | undefined;
   blocks: number | undefined;
+  fileId: string | null;
+}
+
+function fromBackendMetadata(metadata: BackendMetadata): Metadata {
+  const { accessedAtMs, createdAtMs, modifiedAtMs, ...data } = metadata;
+  return {
+    accessedAt: new Date(accessedAtMs),
+    createdAt: new Date(createdAtMs),
+    modifiedAt: new Date(modifiedAtMs),
+    ...data,
+  };
 }
 
 export async function metadata(path: string): Promise<Metadata> {
   return await invoke<BackendMetadata>("plugin:fs-extra|metadata", {
     path,
-  }).then((metadata) => {
-    const { accessedAtMs, createdAtMs, modifiedAtMs, ...data } = metadata;
-    return {
-      accessedAt: new Date(accessedAtMs),
-      createdAt: new Date(createdAtMs),
-      modifiedAt: new Date(modifiedAtMs),
-      ...data,
-    };
-  });
+  }).then(fromBackendMetadata);
 }
 
 export async function exists(path: string): Promise<boolean> {
   return await invoke("plugin:fs-extra|exists", { path });
 }
+
+/**
+ * Rotates the file at `path` if its size is at least `maxSize` bytes.
+ *
+ * The file is renamed to `path.1`, shifting existing rotated files up to `path.{keep}`,
+ * and a new empty file is created in its place.
+ *
+ * @returns The path of the active file.
+ */
+export async function rotateFile(
+  path: string,
+  maxSize: number,
+  keep: number,
+): Promise<string> {
+  return await invoke("plugin:fs-extra|rotate_file", { path, maxSize, keep });
+}
+
+/**
+ * Atomically writes `data` to the file at `path` and reads it back to verify
+ * the file content matches, rejecting if it doesn't.
+ */
+export async function writeVerify(
+  path: string,
+  data: Uint8Array | number[],
+): Promise<void> {
+  return await invoke("plugin:fs-extra|write_verify", {
+    path,
+    data: Array.from(data),
+  });
+}
+
+/**
+ * Increments the integer stored in the file at `path` by `delta` under an exclusive lock
+ * on the file itself, creating the file if it doesn't exist.
+ *
+ * @returns The new value.
+ */
+export async function incrementFile(
+  path: string,
+  delta: number = 1,
+): Promise<number> {
+  return await invoke("plugin:fs-extra|increment_file", { path, delta });
+}
+
+interface ChunkPayload {
+  data: number[];
+}
+
+/**
+ * Reads the file at `path` in chunks of `chunkSize` bytes, calling `onData` for each chunk,
+ * without ever loading the whole file into memory.
+ * `chunkSize` defaults to 64 KiB and must be between 1 byte and 16 MiB.
+ *
+ * If `followSymlinks` is `false`, rejects with a `SymlinkNotFollowed` error if `path` is a symlink.
+ *
+ * @returns The total number of bytes read.
+ */
+export async function readFileStream(
+  path: string,
+  onData: (chunk: Uint8Array) => void,
+  chunkSize?: number,
+  followSymlinks?: boolean,
+): Promise<number> {
+  const id = window.crypto.getRandomValues(new Uint32Array(1))[0];
+
+  const unlisten = await appWindow.listen<ChunkPayload>(
+    `fs-extra://read-chunk/${id}`,
+    ({ payload }) => {
+      onData(Uint8Array.from(payload.data));
+    },
+  );
+
+  try {
+    return await invoke("plugin:fs-extra|read_file_stream", {
+      id,
+      path,
+      chunkSize,
+      followSymlinks,
+    });
+  } finally {
+    unlisten();
+  }
+}
+
+export interface DirEntry {
+  /**
+   * The path of the entry, relative to the directory being read.
+   */
+  path: string;
+  isDir: boolean;
+  isFile: boolean;
+  isSymlink: boolean;
+}
+
+/**
+ * Recursively lists all entries below the directory at `path`.
+ * Entries that aren't allowed by the fs scope are skipped.
+ */
+export async function readDirRecursive(path: string): Promise<DirEntry[]> {
+  return await invoke("plugin:fs-extra|read_dir_recursive", { path });
+}
+
+export interface ReadDirOptions {
+  /**
+   * Only return entries of this kind.
+   */
+  kind?: "file" | "dir";
+  /**
+   * Only return files with one of these extensions, without the leading dot. Case-insensitive.
+   */
+  extensions?: string[];
+  /**
+   * The key to sort the entries by. Defaults to the order of the file system.
+   */
+  sortBy?: "name" | "size" | "modified";
+  /**
+   * Whether to sort in descending order. Defaults to `false`.
+   */
+  descending?: boolean;
+  /**
+   * The maximum number of entries to return, applied after filtering and sorting.
+   */
+  limit?: number;
+}
+
+export interface ReadDirEntry {
+  name: string;
+  isDir: boolean;
+  isFile: boolean;
+  isSymlink: boolean;
+  size: number;
+  modifiedAt: Date;
+}
+
+/**
+ * Lists the entries of the directory at `path`, filtered, sorted and limited in Rust
+ * so only the needed entries cross IPC. Entries that aren't allowed by the fs scope are skipped.
+ */
+export async function readDir(
+  path: string,
+  options: ReadDirOptions = {},
+): Promise<ReadDirEntry[]> {
+  const entries = await invoke<
+    Array<Omit<ReadDirEntry, "modifiedAt"> & { modifiedAtMs: number }>
+  >("plugin:fs-extra|read_dir", { path, options });
+  return entries.map(({ modifiedAtMs, ...entry }) => ({
+    ...entry,
+    modifiedAt: new Date(modifiedAtMs),
+  }));
+}
+
+export interface DetectedText {
+  text: string;
+  /**
+   * The name of the encoding the file was decoded with, e.g. `UTF-8` or `Shift_JIS`.
+   */
+  encoding: string;
+}
+
+export interface ReadTextFileDetectOptions {
+  /**
+   * Whether to read the target if `path` is a symlink, rather than rejecting
+   * with a `SymlinkNotFollowed` error. Defaults to `true`.
+   */
+  followSymlinks?: boolean;
+}
+
+/**
+ * Reads the text file at `path`, detecting its encoding and decoding it to UTF-8.
+ * Falls back to UTF-8, replacing invalid sequences, when the encoding can't be detected reliably.
+ */
+export async function readTextFileDetect(
+  path: string,
+  options: ReadTextFileDetectOptions = {},
+): Promise<DetectedText> {
+  return await invoke("plugin:fs-extra|read_text_file_detect", {
+    path,
+    options,
+  });
+}
+
+export interface GlobOptions {
+  /**
+   * Whether the pattern is matched case-insensitively. Defaults to `false`.
+   */
+  caseInsensitive?: boolean;
+}
+
+/**
+ * Returns the paths below `path` matching the glob `pattern`, e.g. `assets/**\/*.png`.
+ *
+ * The pattern is matched against the whole path relative to `path`, not just the file name.
+ * Paths that aren't allowed by the fs scope are skipped.
+ */
+export async function glob(
+  path: string,
+  pattern: string,
+  options: GlobOptions = {},
+): Promise<string[]> {
+  return await invoke("plugin:fs-extra|glob", { path, pattern, options });
+}
+
+/**
+ * Returns the path of `target` relative to `base`, e.g. `src/main.rs`.
+ *
+ * The path is computed lexically, so the paths don't need to exist.
+ * Returns `null` if there's no relative path between them, e.g. when they are on different Windows drives.
+ */
+export async function relativePath(
+  base: string,
+  target: string,
+): Promise<string | null> {
+  return await invoke("plugin:fs-extra|relative_path", { base, target });
+}
+
+/**
+ * The hash algorithms supported by {@link hashFile}.
+ *
+ * `Sha1`, `Md5` and `Blake3` require the `extra-hashes` cargo feature of the plugin.
+ */
+export type HashAlgorithm = "Sha256" | "Sha1" | "Md5" | "Blake3";
+
+export interface HashFileOptions {
+  /**
+   * Whether to hash the target if `path` is a symlink, rather than rejecting
+   * with a `SymlinkNotFollowed` error. Defaults to `true`.
+   */
+  followSymlinks?: boolean;
+}
+
+/**
+ * Computes the hash of the file at `path` without reading it into JS.
+ *
+ * @returns The lowercase hex digest.
+ */
+export async function hashFile(
+  path: string,
+  algorithm: HashAlgorithm = "Sha256",
+  options: HashFileOptions = {},
+): Promise<string> {
+  return await invoke("plugin:fs-extra|hash_file", {
+    path,
+    algorithm,
+    options,
+  });
+}
+
+export interface CopyProgress {
+  copiedBytes: number;
+  totalBytes: number;
+  /**
+   * The path of the file that was just copied, relative to the source directory.
+   */
+  currentFile: string;
+}
+
+export interface CopyDirOptions {
+  /**
+   * Whether to copy into an existing destination, replacing the files that already exist.
+   * Defaults to `false`, failing if the destination exists.
+   */
+  overwrite?: boolean;
+}
+
+/**
+ * Recursively copies the `fromPath` directory to `toPath`.
+ * Entries that aren't allowed by the fs scope are skipped.
+ *
+ * @returns The total number of bytes copied.
+ */
+export async function copyDir(
+  fromPath: string,
+  toPath: string,
+  options: CopyDirOptions = {},
+  onProgress?: (progress: CopyProgress) => void,
+): Promise<number> {
+  const id = onProgress
+    ? window.crypto.getRandomValues(new Uint32Array(1))[0]
+    : undefined;
+
+  const unlisten = onProgress
+    ? await appWindow.listen<CopyProgress>(
+        `fs-extra://copy-progress/${id}`,
+        ({ payload }) => onProgress(payload),
+      )
+    : undefined;
+
+  try {
+    return await invoke("plugin:fs-extra|copy_dir", {
+      id,
+      fromPath,
+      toPath,
+      options,
+    });
+  } finally {
+    unlisten?.();
+  }
+}
+
+export interface CopyFileProgress {
+  copiedBytes: number;
+  totalBytes: number;
+}
+
+export interface CopyFileOptions {
+  /**
+   * Whether to set the access and modification times of the copy to those of the source.
+   * Defaults to `false`.
+   */
+  preserveTimestamps?: boolean;
+}
+
+/**
+ * Copies the `fromPath` file to `toPath`, replacing it if it exists, along with its permissions.
+ *
+ * If `onProgress` is set, the file is copied in chunks and it is called after each chunk.
+ *
+ * @returns The number of bytes copied.
+ */
+export async function copyFile(
+  fromPath: string,
+  toPath: string,
+  options: CopyFileOptions = {},
+  onProgress?: (progress: CopyFileProgress) => void,
+): Promise<number> {
+  const id = onProgress
+    ? window.crypto.getRandomValues(new Uint32Array(1))[0]
+    : undefined;
+
+  const unlisten = onProgress
+    ? await appWindow.listen<CopyFileProgress>(
+        `fs-extra://copy-file-progress/${id}`,
+        ({ payload }) => onProgress(payload),
+      )
+    : undefined;
+
+  try {
+    return await invoke("plugin:fs-extra|copy_file", {
+      id,
+      fromPath,
+      toPath,
+      options,
+    });
+  } finally {
+    unlisten?.();
+  }
+}
+
+export interface SymlinkOptions {
+  /**
+   * Whether to create a file or a directory symlink. **Windows only**, ignored on other platforms.
+   *
+   * Defaults to the type of the target, or `file` if the target doesn't exist.
+   */
+  kind?: "file" | "dir";
+}
+
+/**
+ * Creates a symlink at `link` pointing to `target`.
+ * A relative `target` is resolved against the directory of `link`.
+ *
+ * On Windows, creating symlinks requires Developer Mode or administrator rights.
+ */
+export async function symlink(
+  target: string,
+  link: string,
+  options: SymlinkOptions = {},
+): Promise<void> {
+  return await invoke("plugin:fs-extra|symlink", { target, link, options });
+}
+
+export interface SetPermissionsOptions {
+  /**
+   * The file mode to set, e.g. `0o644`. **Unix only**, ignored on other platforms.
+   */
+  mode?: number;
+  /**
+   * Whether to set or clear the read-only attribute. **Windows only**, ignored on other platforms.
+   */
+  readonly?: boolean;
+}
+
+/**
+ * Sets the permissions of the file or directory at `path`.
+ *
+ * Send `mode` on Unix and `readonly` on Windows; the field that doesn't apply to the current platform is ignored.
+ */
+export async function setPermissions(
+  path: string,
+  options: SetPermissionsOptions,
+): Promise<void> {
+  return await invoke("plugin:fs-extra|set_permissions", { path, options });
+}
+
+export interface SetTimesOptions {
+  /**
+   * The new access time. Left unchanged if not set.
+   */
+  accessedAt?: Date | number;
+  /**
+   * The new modification time. Left unchanged if not set.
+   */
+  modifiedAt?: Date | number;
+}
+
+/**
+ * Sets the access and modification times of the file at `path`.
+ *
+ * @returns The updated metadata of the file.
+ */
+export async function setTimes(
+  path: string,
+  options: SetTimesOptions,
+): Promise<Metadata> {
+  return await invoke<BackendMetadata>("plugin:fs-extra|set_times", {
+    path,
+    options: {
+      accessedAtMs:
+        options.accessedAt === undefined ? undefined : +options.accessedAt,
+      modifiedAtMs:
+        options.modifiedAt === undefined ? undefined : +options.modifiedAt,
+    },
+  }).then(fromBackendMetadata);
+}
+
+/**
+ * Reads up to `length` bytes of the file at `path`, starting at `offset`.
+ *
+ * Fewer bytes are returned if the end of the file is reached.
+ * If `followSymlinks` is `false`, rejects with a `SymlinkNotFollowed` error if `path` is a symlink.
+ */
+export async function readRange(
+  path: string,
+  offset: number,
+  length: number,
+  followSymlinks?: boolean,
+): Promise<Uint8Array> {
+  const [data] = await invoke<[number[], number]>(
+    "plugin:fs-extra|read_range",
+    { path, offset, length, followSymlinks },
+  );
+  return Uint8Array.from(data);
+}
+
+export interface AppendOptions {
+  /**
+   * The mode of the file if it is created, e.g. `0o644`. **Unix only**, ignored on other platforms.
+   */
+  mode?: number;
+}
+
+/**
+ * Appends `data` to the file at `path`, creating it if it doesn't exist.
+ *
+ * @returns The new length of the file.
+ */
+export async function appendFile(
+  path: string,
+  data: Uint8Array | number[],
+  options: AppendOptions = {},
+): Promise<number> {
+  return await invoke("plugin:fs-extra|append_file", {
+    path,
+    data: Array.from(data),
+    options,
+  });
+}
+
+/**
+ * Appends `text` to the file at `path`, creating it if it doesn't exist.
+ *
+ * @returns The new length of the file.
+ */
+export async function appendTextFile(
+  path: string,
+  text: string,
+  options: AppendOptions = {},
+): Promise<number> {
+  return await invoke("plugin:fs-extra|append_text_file", {
+    path,
+    text,
+    options,
+  });
+}
+
+export interface TempOptions {
+  /**
+   * The directory to create the temporary entry in. Defaults to the system's temporary directory.
+   */
+  dir?: string;
+  /**
+   * The prefix of the entry name. Defaults to `tmp`.
+   */
+  prefix?: string;
+  suffix?: string;
+  /**
+   * Whether to create a directory instead of a file. Defaults to `false`.
+   */
+  directory?: boolean;
+}
+
+/**
+ * Creates a uniquely named temporary file or directory.
+ *
+ * The entry is not removed automatically, the caller is responsible for cleaning it up.
+ *
+ * @returns The path of the created file or directory.
+ */
+export async function createTemp(options: TempOptions = {}): Promise<string> {
+  return await invoke("plugin:fs-extra|create_temp", { options });
+}
+
+/**
+ * Flushes the content and metadata of the file at `path` to disk,
+ * so it survives a crash once the returned promise resolves.
+ */
+export async function fsync(path: string): Promise<void> {
+  return await invoke("plugin:fs-extra|fsync", { path });
+}
+
+/**
+ * Like {@link fsync}, but only flushes the metadata needed to read the content back, e.g. not the access time.
+ */
+export async function fdatasync(path: string): Promise<void> {
+  return await invoke("plugin:fs-extra|fdatasync", { path });
+}
+
+export interface ReadTextFileOptions {
+  /**
+   * The label of the encoding, e.g. `utf-8`, `utf-16le`, `utf-16be` or `latin1`. Defaults to `utf-8`.
+   *
+   * `auto` uses the encoding indicated by a byte order mark, or UTF-8 if there is none.
+   */
+  encoding?: string;
+  /**
+   * Whether to read the target if `path` is a symlink, rather than rejecting
+   * with a `SymlinkNotFollowed` error. Defaults to `true`.
+   */
+  followSymlinks?: boolean;
+}
+
+/**
+ * Reads the text file at `path` in the given encoding.
+ *
+ * Rejects with an error listing the invalid bytes and their offset if the file isn't valid in that encoding.
+ */
+export async function readTextFile(
+  path: string,
+  options: ReadTextFileOptions = {},
+): Promise<string> {
+  return await invoke("plugin:fs-extra|read_text_file", { path, options });
+}
+
+export interface ReadTextLinesOptions {
+  /**
+   * The label of the encoding, see {@link ReadTextFileOptions.encoding}.
+   */
+  encoding?: string;
+  /**
+   * The byte offset to start reading at, which should be the start of a line. Defaults to `0`.
+   */
+  offset?: number;
+  /**
+   * The maximum number of lines to read. Defaults to all remaining lines.
+   */
+  maxLines?: number;
+  /**
+   * Whether to read the target if `path` is a symlink, rather than rejecting
+   * with a `SymlinkNotFollowed` error. Defaults to `true`.
+   */
+  followSymlinks?: boolean;
+}
+
+export interface TextLines {
+  /**
+   * Each line without its line ending, or the error decoding it.
+   */
+  lines: Array<{ line: string } | { error: string }>;
+  /**
+   * The byte offset after the last line read, to pass as `offset` to continue reading.
+   */
+  nextOffset: number;
+}
+
+/**
+ * Reads the lines of the text file at `path` in the given encoding, starting at `offset`.
+ *
+ * Lines with bytes that are invalid in the encoding are returned as errors without failing the others.
+ */
+export async function readTextLines(
+  path: string,
+  options: ReadTextLinesOptions = {},
+): Promise<TextLines> {
+  const { lines, nextOffset } = await invoke<{
+    lines: Array<{ Ok: string } | { Err: string }>;
+    nextOffset: number;
+  }>("plugin:fs-extra|read_text_lines", { path, options });
+  return {
+    lines: lines.map((result) =>
+      "Ok" in result ? { line: result.Ok } : { error: result.Err },
+    ),
+    nextOffset,
+  };
+}
+
+/**
+ * Returns the absolute path of `path` with all symlinks and `.` and `..` components resolved.
+ *
+ * The path must exist, otherwise the promise rejects with a `NotFound` error.
+ * Rejects with a `PermissionDenied` error if the resolved path isn't allowed by the fs scope.
+ */
+export async function canonicalize(path: string): Promise<string> {
+  return await invoke("plugin:fs-extra|canonicalize", { path });
+}
+
+export interface ScopeDebug {
+  /** The path the scope patterns are matched against, canonicalized if it exists. */
+  resolvedPath: string;
+  allowedPatterns: string[];
+  forbiddenPatterns: string[];
+  matchedAllowed: string[];
+  matchedForbidden: string[];
+  verdict: "allowed" | "denied" | "notMatched";
+}
+
+/**
+ * Explains how the fs scope treats `path`, to debug why a path is forbidden.
+ *
+ * Only paths and scope patterns are returned, the file is never read.
+ * Requires the `debug-scope` feature of the plugin, otherwise the promise rejects.
+ */
+export async function debugScope(path: string): Promise<ScopeDebug> {
+  return await invoke("plugin:fs-extra|debug_scope", { path });
+}
+
+export interface StatManyOptions {
+  /**
+   * Whether to return the metadata of the targets of symlinks rather than of the symlinks themselves.
+   * Defaults to `true`.
+   */
+  followSymlinks?: boolean;
+}
+
+/**
+ * Returns the metadata of each of the `paths` in a single call, in the same order as `paths`.
+ *
+ * Each entry holds either the metadata of the path or the error reading it.
+ */
+export async function statMany(
+  paths: string[],
+  options: StatManyOptions = {},
+): Promise<Array<{ metadata: Metadata } | { error: string }>> {
+  const results = await invoke<
+    Array<{ Ok: BackendMetadata } | { Err: string }>
+  >("plugin:fs-extra|stat_many", { paths, options });
+  return results.map((result) =>
+    "Ok" in result
+      ? { metadata: fromBackendMetadata(result.Ok) }
+      : { error: result.Err },
+  );
+}
+
+export interface WriteStreamOptions {
+  /**
+   * Whether to append to the file instead of truncating it. Defaults to `false`.
+   */
+  append?: boolean;
+}
+
+export interface WriteStream {
+  /**
+   * Writes a chunk to the file. Chunks are written in the order the calls are made.
+   *
+   * @returns The number of bytes written to the file so far.
+   */
+  write(data: Uint8Array | number[]): Promise<number>;
+  /**
+   * Flushes the file to disk and closes the stream.
+   *
+   * @returns The total number of bytes written.
+   */
+  close(): Promise<number>;
+}
+
+/**
+ * Opens the file at `path` for writing it in chunks, so large payloads don't have to be sent at once.
+ * The file is created if it doesn't exist.
+ *
+ * If a write fails, the error message includes how many bytes were committed before the failure.
+ */
+export async function openWriteStream(
+  path: string,
+  options: WriteStreamOptions = {},
+): Promise<WriteStream> {
+  const id = window.crypto.getRandomValues(new Uint32Array(1))[0];
+
+  await invoke("plugin:fs-extra|open_write_stream", { id, path, options });
+
+  return {
+    write: async (data) =>
+      await invoke("plugin:fs-extra|write_chunk", {
+        id,
+        data: Array.from(data),
+      }),
+    close: async () =>
+      await invoke("plugin:fs-extra|close_write_stream", { id }),
+  };
+}
+
+export interface DiskUsage {
+  totalBytes: number;
+  freeBytes: number;
+  /**
+   * The free bytes available to the current user, which can be less than `freeBytes`.
+   */
+  availableBytes: number;
+}
+
+/**
+ * Returns the space of the file system containing `path`.
+ *
+ * If `path` doesn't exist, the file system of its nearest existing parent is used,
+ * e.g. to check the available space before creating a file.
+ */
+export async function diskUsage(path: string): Promise<DiskUsage> {
+  return await invoke("plugin:fs-extra|disk_usage", { path });
+}
+
+/**
+ * Replaces the content of the file at `path` atomically, so a crash mid-write never leaves a corrupt file behind.
+ *
+ * The file is created if it doesn't exist, otherwise its permissions are preserved.
+ * Ownership is not preserved on Unix, the file is owned by the current user afterwards.
+ */
+export async function writeFileAtomic(
+  path: string,
+  data: Uint8Array | number[],
+): Promise<void> {
+  return await invoke("plugin:fs-extra|write_file_atomic", {
+    path,
+    data: Array.from(data),
+  });
+}
+
+export interface RemoveOptions {
+  /**
+   * Whether to remove directories with their contents. Defaults to `false`.
+   */
+  recursive?: boolean;
+  /**
+   * Whether to move the path to the system's trash instead of deleting it permanently,
+   * so it can be recovered. Only supported on Windows, macOS and Linux. Defaults to `false`.
+   */
+  trash?: boolean;
+}
+
+/**
+ * Removes the file or directory at `path`.
+ */
+export async function remove(
+  path: string,
+  options: RemoveOptions = {},
+): Promise<void> {
+  return await invoke("plugin:fs-extra|remove", { path, options });
+}
+
+export interface RenameOptions {
+  /**
+   * Whether to reject with an error rather than copying and deleting `fromPath`
+   * when the destination is on another file system. Defaults to `false`.
+   */
+  atomic?: boolean;
+}
+
+/**
+ * Moves `fromPath` to `toPath`.
+ *
+ * Moves to another file system, which can't be done with a rename, fall back to
+ * copying `fromPath` along with its metadata and deleting it afterwards, unless `atomic` is set.
+ */
+export async function rename(
+  fromPath: string,
+  toPath: string,
+  options: RenameOptions = {},
+): Promise<void> {
+  return await invoke("plugin:fs-extra|rename", { fromPath, toPath, options });
+}
+
+export interface MkdirOptions {
+  /**
+   * Whether to create the missing parent directories as well. Defaults to `false`.
+   */
+  recursive?: boolean;
+  /**
+   * The mode of the created directories, e.g. `0o755`. **Unix only**, ignored on other platforms.
+   */
+  mode?: number;
+}
+
+export interface MkdirResult {
+  /**
+   * `false` if the directory already existed.
+   */
+  created: boolean;
+}
+
+/**
+ * Creates the directory at `path`, succeeding if it already exists.
+ *
+ * @returns Whether the directory was created by this call, e.g. to only seed its contents the first time.
+ */
+export async function mkdir(
+  path: string,
+  options: MkdirOptions = {},
+): Promise<MkdirResult> {
+  return await invoke("plugin:fs-extra|mkdir", { path, options });
+}
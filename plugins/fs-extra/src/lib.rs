@@ -2,20 +2,36 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-use serde::{ser::Serializer, Serialize};
+use chardetng::EncodingDetector;
+use encoding_rs::{DecoderResult, Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use filetime::FileTime;
+use fs2::FileExt;
+use globset::GlobBuilder;
+use serde::{
+    ser::{SerializeStruct, Serializer},
+    Deserialize, Serialize,
+};
+use sha2::{Digest, Sha256};
 use tauri::{
     command,
     plugin::{Builder as PluginBuilder, TauriPlugin},
-    Runtime,
+    AppHandle, Manager, Runtime, State, Window,
 };
 
 use std::{
-    path::PathBuf,
+    collections::{HashMap, HashSet},
+    fs::{DirBuilder, File, OpenOptions},
+    io::{BufRead, BufReader, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Mutex,
+    },
     time::{SystemTime, UNIX_EPOCH},
 };
 
 #[cfg(unix)]
-use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
 #[cfg(windows)]
 use std::os::windows::fs::MetadataExt;
 
@@ -25,14 +41,119 @@ type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
+    #[error("path not allowed on the configured scope: {0}")]
+    PathForbidden(PathBuf),
+    #[error("the content read back from {0} doesn't match the written content")]
+    VerificationFailed(PathBuf),
+    #[error("{0} doesn't contain a valid integer")]
+    InvalidCounter(PathBuf),
+    #[error("incrementing the counter in {0} overflowed")]
+    CounterOverflow(PathBuf),
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
+    #[error("destination already exists: {0}")]
+    AlreadyExists(PathBuf),
+    #[error("permission denied creating the symlink {0}, on Windows this requires Developer Mode or administrator rights")]
+    SymlinkPermissionDenied(PathBuf),
+    #[error("symlinks are not supported on this platform")]
+    SymlinkUnsupported,
+    #[error("{0} is a symlink, which is not followed")]
+    SymlinkNotFollowed(PathBuf),
+    #[error("can't atomically move {0} to another file system")]
+    CrossesDevices(PathBuf),
+    #[error("{0} is larger than the maximum read size of {1} bytes")]
+    TooLarge(PathBuf, u64),
+    #[error("unknown encoding: {0}")]
+    UnknownEncoding(String),
+    #[error("{path} is not valid {encoding}, invalid bytes {bytes:02x?} at offset {offset}")]
+    Decode {
+        path: PathBuf,
+        encoding: &'static str,
+        offset: usize,
+        bytes: Vec<u8>,
+    },
+    #[error(
+        "invalid chunk size {0}, it must be between 1 and {} bytes",
+        MAX_CHUNK_SIZE
+    )]
+    InvalidChunkSize(usize),
+    #[error("no write stream with id {0} is open")]
+    UnknownWriteStream(u32),
+    #[error("a write stream with id {0} is already open")]
+    WriteStreamInUse(u32),
+    #[error("writing to {path} failed after {written} bytes were committed: {source}")]
+    PartialWrite {
+        path: PathBuf,
+        written: u64,
+        source: std::io::Error,
+    },
+    #[cfg(any(windows, target_os = "macos", target_os = "linux"))]
+    #[error(transparent)]
+    Trash(#[from] trash::Error),
+    #[error("moving files to the trash is not supported on this platform")]
+    TrashUnsupported,
+    #[error("unknown hash algorithm: {0}")]
+    UnknownHashAlgorithm(String),
+    #[error("the {0} hash algorithm requires the `extra-hashes` feature")]
+    HashAlgorithmDisabled(String),
+    #[error("scope diagnostics require the `debug-scope` feature")]
+    ScopeDebugDisabled,
+}
+
+/// The kind of an [`Error`], serialized along with its message when [`Builder::structured_errors`] is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    AlreadyExists,
+    NotADirectory,
+    /// The path is a symlink but following symlinks was disabled.
+    SymlinkNotFollowed,
+    Other,
+}
+
+/// `ENOTDIR` on Unix, `ERROR_DIRECTORY` on Windows.
+#[cfg(unix)]
+const NOT_A_DIRECTORY_OS_ERROR: i32 = 20;
+#[cfg(windows)]
+const NOT_A_DIRECTORY_OS_ERROR: i32 = 267;
+
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => match e.kind() {
+                std::io::ErrorKind::NotFound => ErrorKind::NotFound,
+                std::io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+                std::io::ErrorKind::AlreadyExists => ErrorKind::AlreadyExists,
+                #[cfg(any(unix, windows))]
+                _ if e.raw_os_error() == Some(NOT_A_DIRECTORY_OS_ERROR) => ErrorKind::NotADirectory,
+                _ => ErrorKind::Other,
+            },
+            Self::PathForbidden(_) | Self::SymlinkPermissionDenied(_) => {
+                ErrorKind::PermissionDenied
+            }
+            Self::AlreadyExists(_) => ErrorKind::AlreadyExists,
+            Self::SymlinkNotFollowed(_) => ErrorKind::SymlinkNotFollowed,
+            _ => ErrorKind::Other,
+        }
+    }
 }
 
+/// Set by [`Builder::structured_errors`], errors are serialized as their message otherwise.
+static STRUCTURED_ERRORS: AtomicBool = AtomicBool::new(false);
+
 impl Serialize for Error {
     fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(self.to_string().as_ref())
+        if !STRUCTURED_ERRORS.load(Ordering::Relaxed) {
+            return serializer.serialize_str(self.to_string().as_ref());
+        }
+        let mut state = serializer.serialize_struct("Error", 2)?;
+        state.serialize_field("kind", &self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
     }
 }
 
@@ -75,6 +196,9 @@ struct Metadata {
     unix: UnixMetadata,
     #[cfg(windows)]
     file_attributes: u32,
+    /// A portable identity of the file, equal for paths referring to the same file, e.g. hard links.
+    /// Made of the device and inode numbers on Unix and the volume serial number and file index on Windows.
+    file_id: Option<String>,
 }
 
 fn system_time_to_ms(time: std::io::Result<SystemTime>) -> u64 {
@@ -85,39 +209,152 @@ fn system_time_to_ms(time: std::io::Result<SystemTime>) -> u64 {
     .unwrap_or_default()
 }
 
+impl From<std::fs::Metadata> for Metadata {
+    fn from(metadata: std::fs::Metadata) -> Self {
+        let file_type = metadata.file_type();
+        let permissions = metadata.permissions();
+        Metadata {
+            accessed_at_ms: system_time_to_ms(metadata.accessed()),
+            created_at_ms: system_time_to_ms(metadata.created()),
+            modified_at_ms: system_time_to_ms(metadata.modified()),
+            is_dir: file_type.is_dir(),
+            is_file: file_type.is_file(),
+            is_symlink: file_type.is_symlink(),
+            size: metadata.len(),
+            permissions: Permissions {
+                readonly: permissions.readonly(),
+                #[cfg(unix)]
+                mode: permissions.mode(),
+            },
+            #[cfg(unix)]
+            unix: UnixMetadata {
+                dev: metadata.dev(),
+                ino: metadata.ino(),
+                mode: metadata.mode(),
+                nlink: metadata.nlink(),
+                uid: metadata.uid(),
+                gid: metadata.gid(),
+                rdev: metadata.rdev(),
+                blksize: metadata.blksize(),
+                blocks: metadata.blocks(),
+            },
+            #[cfg(windows)]
+            file_attributes: metadata.file_attributes(),
+            #[cfg(unix)]
+            file_id: Some(format!("{:x}-{:x}", metadata.dev(), metadata.ino())),
+            // requires a handle to the file, see `read_metadata`
+            #[cfg(not(unix))]
+            file_id: None,
+        }
+    }
+}
+
+/// Returns the volume serial number and file index of the file as its identity.
+#[cfg(windows)]
+fn windows_file_id(path: &Path, follow_symlinks: bool) -> Option<String> {
+    use std::os::windows::{fs::OpenOptionsExt, io::AsRawHandle};
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION, FILE_FLAG_BACKUP_SEMANTICS,
+        FILE_FLAG_OPEN_REPARSE_POINT,
+    };
+
+    // backup semantics are required to open directories
+    let mut flags = FILE_FLAG_BACKUP_SEMANTICS;
+    if !follow_symlinks {
+        flags |= FILE_FLAG_OPEN_REPARSE_POINT;
+    }
+    // no access rights are needed to query the file information
+    let file = OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(flags)
+        .open(path)
+        .ok()?;
+
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    if unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) } == 0 {
+        return None;
+    }
+    let index = (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow);
+    Some(format!("{:x}-{:x}", info.dwVolumeSerialNumber, index))
+}
+
+fn read_metadata(path: &Path, follow_symlinks: bool) -> Result<Metadata> {
+    let metadata = if follow_symlinks {
+        std::fs::metadata(path)?
+    } else {
+        std::fs::symlink_metadata(path)?
+    };
+    #[allow(unused_mut)]
+    let mut metadata = Metadata::from(metadata);
+    #[cfg(windows)]
+    {
+        metadata.file_id = windows_file_id(path, follow_symlinks);
+    }
+    Ok(metadata)
+}
+
 #[command]
 async fn metadata(path: PathBuf) -> Result<Metadata> {
-    let metadata = std::fs::metadata(path)?;
-    let file_type = metadata.file_type();
-    let permissions = metadata.permissions();
-    Ok(Metadata {
-        accessed_at_ms: system_time_to_ms(metadata.accessed()),
-        created_at_ms: system_time_to_ms(metadata.created()),
-        modified_at_ms: system_time_to_ms(metadata.modified()),
-        is_dir: file_type.is_dir(),
-        is_file: file_type.is_file(),
-        is_symlink: file_type.is_symlink(),
-        size: metadata.len(),
-        permissions: Permissions {
-            readonly: permissions.readonly(),
-            #[cfg(unix)]
-            mode: permissions.mode(),
-        },
-        #[cfg(unix)]
-        unix: UnixMetadata {
-            dev: metadata.dev(),
-            ino: metadata.ino(),
-            mode: metadata.mode(),
-            nlink: metadata.nlink(),
-            uid: metadata.uid(),
-            gid: metadata.gid(),
-            rdev: metadata.rdev(),
-            blksize: metadata.blksize(),
-            blocks: metadata.blocks(),
-        },
-        #[cfg(windows)]
-        file_attributes: metadata.file_attributes(),
-    })
+    read_metadata(&path, true)
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct StatManyOptions {
+    /// Whether to return the metadata of the targets of symlinks rather than of the symlinks themselves.
+    follow_symlinks: Option<bool>,
+}
+
+/// Returns the metadata of each path, or the error reading it, in the same order as `paths`.
+#[command]
+async fn stat_many<R: Runtime>(
+    app: AppHandle<R>,
+    paths: Vec<PathBuf>,
+    options: Option<StatManyOptions>,
+) -> Vec<std::result::Result<Metadata, String>> {
+    let follow_symlinks = options.unwrap_or_default().follow_symlinks.unwrap_or(true);
+    paths
+        .into_iter()
+        .map(|path| {
+            ensure_allowed(&app, &path)?;
+            read_metadata(&path, follow_symlinks)
+        })
+        .map(|result: Result<Metadata>| result.map_err(|e| e.to_string()))
+        .collect()
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SetTimesOptions {
+    /// The new access time in milliseconds since the Unix epoch, left unchanged if not set.
+    accessed_at_ms: Option<u64>,
+    /// The new modification time in milliseconds since the Unix epoch, left unchanged if not set.
+    modified_at_ms: Option<u64>,
+}
+
+fn ms_to_file_time(ms: u64) -> FileTime {
+    FileTime::from_unix_time((ms / 1000) as i64, (ms % 1000) as u32 * 1_000_000)
+}
+
+/// Sets the access and modification times of `path`, returning its updated metadata.
+#[command]
+async fn set_times<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: SetTimesOptions,
+) -> Result<Metadata> {
+    ensure_allowed(&app, &path)?;
+
+    match (options.accessed_at_ms, options.modified_at_ms) {
+        (Some(atime), Some(mtime)) => {
+            filetime::set_file_times(&path, ms_to_file_time(atime), ms_to_file_time(mtime))?
+        }
+        (Some(atime), None) => filetime::set_file_atime(&path, ms_to_file_time(atime))?,
+        (None, Some(mtime)) => filetime::set_file_mtime(&path, ms_to_file_time(mtime))?,
+        (None, None) => {}
+    }
+
+    metadata(path).await
 }
 
 #[command]
@@ -125,8 +362,2307 @@ async fn exists(path: PathBuf) -> bool {
     path.exists()
 }
 
-pub fn init<R: Runtime>() -> TauriPlugin<R> {
-    PluginBuilder::new("fs-extra")
-        .invoke_handler(tauri::generate_handler![exists, metadata])
-        .build()
+fn ensure_allowed<R: Runtime, M: Manager<R>>(manager: &M, path: &Path) -> Result<()> {
+    if manager.fs_scope().is_allowed(path) {
+        Ok(())
+    } else {
+        Err(Error::PathForbidden(path.to_path_buf()))
+    }
+}
+
+/// The plugin configuration set on the [`Builder`].
+#[derive(Default)]
+struct Config {
+    max_read_bytes: Option<u64>,
+}
+
+/// Opens the file for reading. If `follow_symlinks` is `false`, opening fails with
+/// [`Error::SymlinkNotFollowed`] if `path` itself is a symlink, without a window for it to be swapped in.
+fn open_file(path: &Path, follow_symlinks: bool) -> Result<File> {
+    if follow_symlinks {
+        return Ok(File::open(path)?);
+    }
+
+    #[cfg(unix)]
+    return OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOFOLLOW)
+        .open(path)
+        .map_err(|e| match e.raw_os_error() {
+            Some(libc::ELOOP) => Error::SymlinkNotFollowed(path.to_path_buf()),
+            _ => e.into(),
+        });
+
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        use windows_sys::Win32::Storage::FileSystem::FILE_FLAG_OPEN_REPARSE_POINT;
+
+        // opens the symlink itself rather than its target
+        let file = OpenOptions::new()
+            .read(true)
+            .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT)
+            .open(path)?;
+        if file.metadata()?.file_type().is_symlink() {
+            return Err(Error::SymlinkNotFollowed(path.to_path_buf()));
+        }
+        return Ok(file);
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    {
+        if std::fs::symlink_metadata(path)?.file_type().is_symlink() {
+            return Err(Error::SymlinkNotFollowed(path.to_path_buf()));
+        }
+        Ok(File::open(path)?)
+    }
+}
+
+/// Reads the whole file, failing if it is larger than the maximum read size.
+fn read_limited<R: Runtime>(
+    app: &AppHandle<R>,
+    path: &Path,
+    follow_symlinks: bool,
+) -> Result<Vec<u8>> {
+    let file = open_file(path, follow_symlinks)?;
+    read_to_end_limited(file, path, app.state::<Config>().max_read_bytes)
+}
+
+fn read_to_end_limited(mut file: File, path: &Path, max: Option<u64>) -> Result<Vec<u8>> {
+    let max = match max {
+        Some(max) => max,
+        None => {
+            let mut data = Vec::new();
+            file.read_to_end(&mut data)?;
+            return Ok(data);
+        }
+    };
+
+    if file.metadata()?.len() > max {
+        return Err(Error::TooLarge(path.to_path_buf(), max));
+    }
+    // the reported length can't be trusted for special files, e.g. `/dev/zero`
+    let mut data = Vec::new();
+    file.take(max + 1).read_to_end(&mut data)?;
+    if data.len() as u64 > max {
+        return Err(Error::TooLarge(path.to_path_buf(), max));
+    }
+
+    Ok(data)
+}
+
+/// Serializes rotations so the size check and the renames can't interleave.
+static ROTATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Appends `suffix` to the file name of `path`.
+fn path_with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    path_with_suffix(path, &format!(".{index}"))
+}
+
+#[command]
+async fn rotate_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    max_size: u64,
+    keep: usize,
+) -> Result<PathBuf> {
+    ensure_allowed(&app, &path)?;
+    let rotated = (1..=keep)
+        .map(|i| rotated_path(&path, i))
+        .collect::<Vec<_>>();
+    for p in &rotated {
+        ensure_allowed(&app, p)?;
+    }
+
+    let _guard = ROTATE_LOCK.lock().unwrap();
+
+    match std::fs::metadata(&path) {
+        Ok(metadata) if metadata.len() >= max_size => {}
+        Ok(_) => return Ok(path),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(path),
+        Err(e) => return Err(e.into()),
+    }
+
+    if let Some(oldest) = rotated.last() {
+        if oldest.exists() {
+            std::fs::remove_file(oldest)?;
+        }
+        // shift `name.N-1` to `name.N`, ..., `name.1` to `name.2`
+        for pair in rotated.windows(2).rev() {
+            if pair[0].exists() {
+                std::fs::rename(&pair[0], &pair[1])?;
+            }
+        }
+        std::fs::rename(&path, &rotated[0])?;
+    }
+
+    // start fresh, this also truncates the file when nothing is kept
+    File::create(&path)?;
+
+    Ok(path)
+}
+
+/// `EXDEV` on Unix, `ERROR_NOT_SAME_DEVICE` on Windows.
+#[cfg(unix)]
+const CROSSES_DEVICES_OS_ERROR: i32 = 18;
+#[cfg(windows)]
+const CROSSES_DEVICES_OS_ERROR: i32 = 17;
+
+/// Writes `data` to a temporary file next to `path` and moves it over `path` once it is
+/// fully written and synced, so readers never observe a partially written file.
+///
+/// The permissions of an existing file at `path` are preserved.
+fn write_atomic(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    // a unique name so concurrent writes don't share the temporary file, opened like
+    // `File::create` rather than `NamedTempFile::new_in` so new files get the default permissions
+    let mut file = tempfile::Builder::new()
+        .prefix(".tmp")
+        .make_in(dir, |tmp_path| {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(tmp_path)
+        })?;
+    file.write_all(data)?;
+    file.as_file().sync_all()?;
+    // after writing, since the permissions may make the file read-only
+    if let Ok(metadata) = std::fs::metadata(path) {
+        file.as_file().set_permissions(metadata.permissions())?;
+    }
+
+    // the temporary file is removed when the persist error is dropped
+    match file.persist(path).map_err(|e| e.error) {
+        Ok(_) => Ok(()),
+        // only possible if `path` is a mount point of its own, e.g. a bind mounted file
+        #[cfg(any(unix, windows))]
+        Err(e) if e.raw_os_error() == Some(CROSSES_DEVICES_OS_ERROR) => {
+            log::warn!(
+                "fs-extra: can't atomically replace {} across devices, writing it in place",
+                path.display()
+            );
+            std::fs::write(path, data)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Replaces the content of the file atomically, so a crash mid-write never leaves it corrupt.
+/// The file is created if it doesn't exist, or keeps its permissions otherwise.
+#[command]
+async fn write_file_atomic<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    data: Vec<u8>,
+) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+    write_atomic(&path, &data)?;
+    Ok(())
+}
+
+#[command]
+async fn write_verify<R: Runtime>(app: AppHandle<R>, path: PathBuf, data: Vec<u8>) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+    write_atomic(&path, &data)?;
+
+    // read back through a fresh handle rather than the one used for writing
+    let written = std::fs::read(&path)?;
+    if written != data {
+        return Err(Error::VerificationFailed(path));
+    }
+
+    Ok(())
+}
+
+/// Adds `delta` to the integer counter stored in the file at `path`, creating it if it doesn't exist.
+///
+/// The file itself is locked, so it is updated in place rather than replaced,
+/// which would leave the instances waiting for the lock with the replaced file.
+fn increment(path: &Path, delta: i64) -> Result<i64> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)?;
+    // released when `file` is dropped
+    file.lock_exclusive()?;
+
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    let current = match content.trim() {
+        "" => 0,
+        content => content
+            .parse::<i64>()
+            .map_err(|_| Error::InvalidCounter(path.to_path_buf()))?,
+    };
+    let value = current
+        .checked_add(delta)
+        .ok_or_else(|| Error::CounterOverflow(path.to_path_buf()))?;
+
+    // pad the value to the previous length and truncate afterwards, so a crash in between
+    // leaves the new value followed by whitespace rather than a mix of both values
+    let text = value.to_string();
+    file.seek(SeekFrom::Start(0))?;
+    file.write_all(format!("{:<1$}", text, content.len()).as_bytes())?;
+    file.set_len(text.len() as u64)?;
+    file.sync_all()?;
+
+    Ok(value)
+}
+
+#[command]
+async fn increment_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    delta: Option<i64>,
+) -> Result<i64> {
+    ensure_allowed(&app, &path)?;
+    increment(&path, delta.unwrap_or(1))
+}
+
+/// Default chunk size of [`read_file_stream`], 64 KiB.
+const DEFAULT_CHUNK_SIZE: usize = 64 * 1024;
+/// Maximum chunk size of [`read_file_stream`], 16 MiB.
+const MAX_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Serialize)]
+struct ChunkPayload {
+    data: Vec<u8>,
+}
+
+/// Reads the file in chunks and emits each chunk to the calling window
+/// so the whole file never has to be held in memory.
+///
+/// Returns the total number of bytes read once the end of the file is reached.
+#[command]
+async fn read_file_stream<R: Runtime>(
+    window: Window<R>,
+    id: u32,
+    path: PathBuf,
+    chunk_size: Option<usize>,
+    follow_symlinks: Option<bool>,
+) -> Result<u64> {
+    ensure_allowed(&window, &path)?;
+    let chunk_size = chunk_size.unwrap_or(DEFAULT_CHUNK_SIZE);
+    if chunk_size == 0 || chunk_size > MAX_CHUNK_SIZE {
+        return Err(Error::InvalidChunkSize(chunk_size));
+    }
+
+    let event_name = format!("fs-extra://read-chunk/{id}");
+    let mut file = open_file(&path, follow_symlinks.unwrap_or(true))?;
+    let mut buf = vec![0; chunk_size];
+    let mut total = 0;
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        total += n as u64;
+        let _ = window.emit(
+            &event_name,
+            ChunkPayload {
+                data: buf[..n].to_vec(),
+            },
+        );
+    }
+
+    Ok(total)
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct AppendOptions {
+    /// The mode of the file if it is created. Only used on Unix.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    mode: Option<u32>,
+}
+
+fn append(path: &Path, data: &[u8], options: AppendOptions) -> Result<u64> {
+    let mut open_options = OpenOptions::new();
+    open_options.append(true).create(true);
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        open_options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = options;
+
+    let mut file = open_options.open(path)?;
+    file.write_all(data)?;
+    Ok(file.metadata()?.len())
+}
+
+/// Appends `data` to the file, creating it if it doesn't exist. Returns the new length of the file.
+#[command]
+async fn append_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    data: Vec<u8>,
+    options: Option<AppendOptions>,
+) -> Result<u64> {
+    ensure_allowed(&app, &path)?;
+    append(&path, &data, options.unwrap_or_default())
+}
+
+/// Appends `text` to the file, creating it if it doesn't exist. Returns the new length of the file.
+#[command]
+async fn append_text_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    text: String,
+    options: Option<AppendOptions>,
+) -> Result<u64> {
+    ensure_allowed(&app, &path)?;
+    append(&path, text.as_bytes(), options.unwrap_or_default())
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct TempOptions {
+    /// The directory to create the temporary entry in, defaults to the system's temporary directory.
+    dir: Option<PathBuf>,
+    /// Defaults to `tmp`, a leading dot would make the entry hidden and rejected by the scope.
+    prefix: Option<String>,
+    suffix: Option<String>,
+    /// Whether to create a directory instead of a file.
+    directory: bool,
+}
+
+/// Creates a uniquely named temporary file or directory and returns its path.
+///
+/// The entry is not removed automatically, the caller is responsible for cleaning it up.
+#[command]
+async fn create_temp<R: Runtime>(
+    app: AppHandle<R>,
+    options: Option<TempOptions>,
+) -> Result<PathBuf> {
+    let options = options.unwrap_or_default();
+    let dir = options.dir.unwrap_or_else(std::env::temp_dir);
+    ensure_allowed(&app, &dir)?;
+
+    let mut builder = tempfile::Builder::new();
+    builder
+        .prefix(options.prefix.as_deref().unwrap_or("tmp"))
+        .suffix(options.suffix.as_deref().unwrap_or_default());
+
+    // the name is created exclusively and retried with a new random component on collisions
+    let path = if options.directory {
+        builder.tempdir_in(&dir)?.into_path()
+    } else {
+        builder.tempfile_in(&dir)?.keep().map_err(|e| e.error)?.1
+    };
+
+    // the prefix or suffix could have moved the entry out of the scope
+    if let Err(e) = ensure_allowed(&app, &path) {
+        let _ = if options.directory {
+            std::fs::remove_dir(&path)
+        } else {
+            std::fs::remove_file(&path)
+        };
+        return Err(e);
+    }
+
+    Ok(path)
+}
+
+/// Opens the file for flushing it, which requires write access on Windows.
+fn open_for_sync(path: &Path) -> std::io::Result<File> {
+    #[cfg(windows)]
+    return OpenOptions::new().write(true).open(path);
+    #[cfg(not(windows))]
+    File::open(path)
+}
+
+/// Flushes the content and metadata of the file to disk.
+#[command]
+async fn fsync<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+    open_for_sync(&path)?.sync_all()?;
+    Ok(())
+}
+
+/// Flushes the content of the file to disk, without the metadata that isn't needed to read it back.
+#[command]
+async fn fdatasync<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+    open_for_sync(&path)?.sync_data()?;
+    Ok(())
+}
+
+/// Reads up to `length` bytes of the file starting at `offset`.
+///
+/// Returns the bytes read and their number, which is less than `length` if the end of the file was reached.
+#[command]
+async fn read_range<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    offset: u64,
+    length: u32,
+    follow_symlinks: Option<bool>,
+) -> Result<(Vec<u8>, usize)> {
+    ensure_allowed(&app, &path)?;
+    if let Some(max) = app.state::<Config>().max_read_bytes {
+        if u64::from(length) > max {
+            return Err(Error::TooLarge(path, max));
+        }
+    }
+
+    let mut file = open_file(&path, follow_symlinks.unwrap_or(true))?;
+    // never allocate more than the file can actually return from `offset`
+    let available = file.metadata()?.len().saturating_sub(offset);
+    let capacity = u64::from(length).min(available);
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(capacity as usize);
+    let n = file.take(u64::from(length)).read_to_end(&mut data)?;
+
+    Ok((data, n))
+}
+
+struct WriteStream {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+/// The files opened with [`open_write_stream`], keyed by the id chosen by the frontend.
+#[derive(Default)]
+struct WriteStreams(Mutex<HashMap<u32, WriteStream>>);
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct WriteStreamOptions {
+    /// Whether to append to the file instead of truncating it.
+    append: bool,
+}
+
+/// Opens the file for writing it in chunks with [`write_chunk`], creating it if it doesn't exist.
+///
+/// Fails if a stream with the same id is still open, instead of closing it.
+#[command]
+async fn open_write_stream<R: Runtime>(
+    app: AppHandle<R>,
+    streams: State<'_, WriteStreams>,
+    id: u32,
+    path: PathBuf,
+    options: Option<WriteStreamOptions>,
+) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+
+    // checked before opening the file so it isn't truncated for nothing
+    let mut streams = streams.0.lock().unwrap();
+    if streams.contains_key(&id) {
+        return Err(Error::WriteStreamInUse(id));
+    }
+
+    let append = options.unwrap_or_default().append;
+    let file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .append(append)
+        .truncate(!append)
+        .open(&path)?;
+    streams.insert(
+        id,
+        WriteStream {
+            path,
+            file,
+            written: 0,
+        },
+    );
+    Ok(())
+}
+
+/// Writes a chunk to the stream, returning the number of bytes written to it so far.
+#[command]
+async fn write_chunk(streams: State<'_, WriteStreams>, id: u32, data: Vec<u8>) -> Result<u64> {
+    // the lock is held for the whole chunk so chunks are never interleaved
+    let mut streams = streams.0.lock().unwrap();
+    let stream = streams.get_mut(&id).ok_or(Error::UnknownWriteStream(id))?;
+
+    let mut remaining = &data[..];
+    while !remaining.is_empty() {
+        match stream.file.write(remaining) {
+            Ok(0) => {
+                return Err(Error::PartialWrite {
+                    path: stream.path.clone(),
+                    written: stream.written,
+                    source: std::io::ErrorKind::WriteZero.into(),
+                })
+            }
+            Ok(n) => {
+                stream.written += n as u64;
+                remaining = &remaining[n..];
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => {
+                return Err(Error::PartialWrite {
+                    path: stream.path.clone(),
+                    written: stream.written,
+                    source: e,
+                })
+            }
+        }
+    }
+
+    Ok(stream.written)
+}
+
+/// Flushes the stream to disk and closes it, returning the total number of bytes written.
+#[command]
+async fn close_write_stream(streams: State<'_, WriteStreams>, id: u32) -> Result<u64> {
+    let stream = streams
+        .0
+        .lock()
+        .unwrap()
+        .remove(&id)
+        .ok_or(Error::UnknownWriteStream(id))?;
+    stream.file.sync_all()?;
+    Ok(stream.written)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DirEntry {
+    /// The path of the entry, relative to the directory being read.
+    path: PathBuf,
+    is_dir: bool,
+    is_file: bool,
+    is_symlink: bool,
+}
+
+/// Lists all entries below `path`, skipping the ones that aren't allowed by the scope.
+///
+/// Symlinked directories are followed unless they point to a directory that was already visited.
+fn walk_dir<R: Runtime>(app: &AppHandle<R>, path: &Path) -> Result<Vec<DirEntry>> {
+    ensure_allowed(app, path)?;
+    let scope = app.fs_scope();
+    walk_dir_filtered(path, |entry_path| scope.is_allowed(entry_path))
+}
+
+/// Lists all entries below `path` for which `is_allowed` returns `true`, see [`walk_dir`].
+fn walk_dir_filtered(path: &Path, is_allowed: impl Fn(&Path) -> bool) -> Result<Vec<DirEntry>> {
+    let mut entries = Vec::new();
+    let mut visited = HashSet::new();
+    visited.insert(std::fs::canonicalize(path)?);
+    let mut dirs = vec![path.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        // only the root has to be readable, entries that can't be read, e.g. because they were
+        // removed during the walk or lack permissions, are skipped rather than failing the walk
+        let read_dir = match std::fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(e) if dir == path => return Err(e.into()),
+            Err(_) => continue,
+        };
+        for entry in read_dir {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+            let entry_path = entry.path();
+            if !is_allowed(&entry_path) {
+                continue;
+            }
+
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(_) => continue,
+            };
+            let is_symlink = file_type.is_symlink();
+            let (is_dir, is_file) = if is_symlink {
+                match std::fs::metadata(&entry_path) {
+                    Ok(metadata) => (metadata.is_dir(), metadata.is_file()),
+                    // dangling symlink
+                    Err(_) => (false, false),
+                }
+            } else {
+                (file_type.is_dir(), file_type.is_file())
+            };
+
+            // guard against symlink cycles
+            if is_dir
+                && std::fs::canonicalize(&entry_path)
+                    .map_or(false, |canonical| visited.insert(canonical))
+            {
+                dirs.push(entry_path.clone());
+            }
+
+            entries.push(DirEntry {
+                path: entry_path
+                    .strip_prefix(path)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(entry_path),
+                is_dir,
+                is_file,
+                is_symlink,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+#[command]
+async fn read_dir_recursive<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<Vec<DirEntry>> {
+    walk_dir(&app, &path)
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum DirSortKey {
+    Name,
+    Size,
+    Modified,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum DirEntryKind {
+    File,
+    Dir,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ReadDirOptions {
+    /// Only return entries of this kind.
+    kind: Option<DirEntryKind>,
+    /// Only return files with one of these extensions, without the leading dot and case-insensitive.
+    extensions: Vec<String>,
+    /// The key to sort the entries by, they are returned in the order of the file system if not set.
+    sort_by: Option<DirSortKey>,
+    descending: bool,
+    /// The maximum number of entries to return, applied after filtering and sorting.
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReadDirEntry {
+    name: String,
+    is_dir: bool,
+    is_file: bool,
+    is_symlink: bool,
+    size: u64,
+    modified_at_ms: u64,
+}
+
+/// Sorts the entries by `key`, breaking ties by name so the order is stable across calls.
+fn sort_dir_entries(entries: &mut [ReadDirEntry], key: DirSortKey, descending: bool) {
+    entries.sort_by(|a, b| {
+        let ordering = match key {
+            DirSortKey::Name => a.name.cmp(&b.name),
+            DirSortKey::Size => a.size.cmp(&b.size).then_with(|| a.name.cmp(&b.name)),
+            DirSortKey::Modified => a
+                .modified_at_ms
+                .cmp(&b.modified_at_ms)
+                .then_with(|| a.name.cmp(&b.name)),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// Lists the entries of the directory at `path`, filtered, sorted and limited as requested,
+/// so only the needed entries are sent to the frontend. Entries that aren't allowed by the scope are skipped.
+#[command]
+async fn read_dir<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<ReadDirOptions>,
+) -> Result<Vec<ReadDirEntry>> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+
+    let mut entries = Vec::new();
+    for entry in std::fs::read_dir(&path)? {
+        let entry = entry?;
+        let entry_path = entry.path();
+        if !app.fs_scope().is_allowed(&entry_path) {
+            continue;
+        }
+
+        let is_symlink = entry.file_type()?.is_symlink();
+        // the metadata of the target for symlinks, falling back to the link itself if it's dangling
+        let metadata = match is_symlink {
+            true => std::fs::metadata(&entry_path).or_else(|_| entry.metadata())?,
+            false => entry.metadata()?,
+        };
+        let is_dir = metadata.is_dir();
+
+        match options.kind {
+            Some(DirEntryKind::File) if is_dir => continue,
+            Some(DirEntryKind::Dir) if !is_dir => continue,
+            _ => {}
+        }
+        if !options.extensions.is_empty() {
+            let matches = !is_dir
+                && entry_path
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    .map_or(false, |extension| {
+                        options
+                            .extensions
+                            .iter()
+                            .any(|e| e.eq_ignore_ascii_case(extension))
+                    });
+            if !matches {
+                continue;
+            }
+        }
+
+        entries.push(ReadDirEntry {
+            name: entry.file_name().to_string_lossy().into_owned(),
+            is_dir,
+            is_file: metadata.is_file(),
+            is_symlink,
+            size: metadata.len(),
+            modified_at_ms: system_time_to_ms(metadata.modified()),
+        });
+    }
+
+    if let Some(key) = options.sort_by {
+        sort_dir_entries(&mut entries, key, options.descending);
+    }
+    if let Some(limit) = options.limit {
+        entries.truncate(limit);
+    }
+
+    Ok(entries)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DetectedText {
+    text: String,
+    /// The name of the encoding the file was decoded with.
+    encoding: &'static str,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ReadTextFileDetectOptions {
+    /// Whether to read the target if `path` is a symlink, rather than failing. Defaults to `true`.
+    follow_symlinks: Option<bool>,
+}
+
+/// Reads a text file of unknown encoding, detecting its encoding and decoding it to UTF-8.
+#[command]
+async fn read_text_file_detect<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<ReadTextFileDetectOptions>,
+) -> Result<DetectedText> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+    let bytes = read_limited(&app, &path, options.follow_symlinks.unwrap_or(true))?;
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(&bytes, true);
+    let (guess, confident) = detector.guess_assess(None, true);
+    // fall back to lossy UTF-8 if the guess is likely to be wrong
+    let guess = if confident { guess } else { UTF_8 };
+
+    // a BOM takes precedence over the guessed encoding
+    let (text, encoding, _) = guess.decode(&bytes);
+
+    Ok(DetectedText {
+        text: text.into_owned(),
+        encoding: encoding.name(),
+    })
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyProgress {
+    copied_bytes: u64,
+    total_bytes: u64,
+    /// The path of the file that was just copied, relative to the source directory.
+    current_file: PathBuf,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct CopyDirOptions {
+    /// Whether to copy into an existing destination, replacing the files that already exist.
+    overwrite: bool,
+}
+
+/// Recursively copies the `from_path` directory to `to_path`, skipping the entries for which `is_allowed`
+/// returns `false` and failing if a destination isn't allowed. `on_progress` is called after each copied file.
+///
+/// Returns the total number of bytes copied.
+fn copy_dir_filtered(
+    from_path: &Path,
+    to_path: &Path,
+    overwrite: bool,
+    is_allowed: impl Fn(&Path) -> bool,
+    mut on_progress: impl FnMut(CopyProgress),
+) -> Result<u64> {
+    if !is_allowed(to_path) {
+        return Err(Error::PathForbidden(to_path.to_path_buf()));
+    }
+    if !overwrite && to_path.exists() {
+        return Err(Error::AlreadyExists(to_path.to_path_buf()));
+    }
+
+    let entries = walk_dir_filtered(from_path, &is_allowed)?;
+    let mut total_bytes = 0;
+    for entry in entries.iter().filter(|entry| entry.is_file) {
+        total_bytes += std::fs::metadata(from_path.join(&entry.path))?.len();
+    }
+
+    let mut copied_bytes = 0;
+    std::fs::create_dir_all(to_path)?;
+    for entry in entries {
+        let to = to_path.join(&entry.path);
+        if !is_allowed(&to) {
+            return Err(Error::PathForbidden(to));
+        }
+
+        if entry.is_dir {
+            std::fs::create_dir_all(&to)?;
+        } else if entry.is_file {
+            if let Some(parent) = to.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            copied_bytes += std::fs::copy(from_path.join(&entry.path), &to)?;
+            on_progress(CopyProgress {
+                copied_bytes,
+                total_bytes,
+                current_file: entry.path,
+            });
+        }
+    }
+
+    Ok(copied_bytes)
+}
+
+/// Recursively copies the `from_path` directory to `to_path`, skipping the entries that aren't allowed by the scope.
+///
+/// If `id` is set, the progress is emitted to the calling window after each copied file.
+/// Returns the total number of bytes copied.
+#[command]
+async fn copy_dir<R: Runtime>(
+    window: Window<R>,
+    id: Option<u32>,
+    from_path: PathBuf,
+    to_path: PathBuf,
+    options: Option<CopyDirOptions>,
+) -> Result<u64> {
+    let options = options.unwrap_or_default();
+    ensure_allowed(&window, &from_path)?;
+    let scope = window.fs_scope();
+    let event_name = id.map(|id| format!("fs-extra://copy-progress/{id}"));
+    copy_dir_filtered(
+        &from_path,
+        &to_path,
+        options.overwrite,
+        |path| scope.is_allowed(path),
+        |progress| {
+            if let Some(event_name) = &event_name {
+                let _ = window.emit(event_name, progress);
+            }
+        },
+    )
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CopyFileProgress {
+    copied_bytes: u64,
+    total_bytes: u64,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct CopyFileOptions {
+    /// Whether to set the access and modification times of the copy to those of the source.
+    preserve_timestamps: bool,
+}
+
+/// Copies the `from_path` file to `to_path`, replacing it if it exists, along with its permissions.
+///
+/// If `id` is set, the file is copied in chunks and the progress is emitted to the calling window after each chunk.
+/// Returns the number of bytes copied.
+#[command]
+async fn copy_file<R: Runtime>(
+    window: Window<R>,
+    id: Option<u32>,
+    from_path: PathBuf,
+    to_path: PathBuf,
+    options: Option<CopyFileOptions>,
+) -> Result<u64> {
+    let options = options.unwrap_or_default();
+    ensure_allowed(&window, &from_path)?;
+    ensure_allowed(&window, &to_path)?;
+
+    let metadata = std::fs::metadata(&from_path)?;
+    let copied_bytes = match id {
+        // without progress reporting let the platform copy the file, which may be faster
+        None => std::fs::copy(&from_path, &to_path)?,
+        Some(id) => {
+            let event_name = format!("fs-extra://copy-file-progress/{id}");
+            let total_bytes = metadata.len();
+            let mut from = File::open(&from_path)?;
+            let mut to = File::create(&to_path)?;
+            let mut buf = vec![0; DEFAULT_CHUNK_SIZE];
+            let mut copied_bytes = 0;
+            loop {
+                let n = match from.read(&mut buf) {
+                    Ok(0) => break,
+                    Ok(n) => n,
+                    // retried like `io::copy` does, e.g. when a signal arrives mid-read
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                to.write_all(&buf[..n])?;
+                copied_bytes += n as u64;
+                let _ = window.emit(
+                    &event_name,
+                    CopyFileProgress {
+                        copied_bytes,
+                        total_bytes,
+                    },
+                );
+            }
+            to.flush()?;
+            drop(to);
+            std::fs::set_permissions(&to_path, metadata.permissions())?;
+            copied_bytes
+        }
+    };
+
+    if options.preserve_timestamps {
+        filetime::set_file_times(
+            &to_path,
+            FileTime::from_last_access_time(&metadata),
+            FileTime::from_last_modification_time(&metadata),
+        )?;
+    }
+
+    Ok(copied_bytes)
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SetPermissionsOptions {
+    /// The file mode to set. Only used on Unix.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    mode: Option<u32>,
+    /// Whether to set or clear the read-only attribute. Only used on Windows.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    readonly: Option<bool>,
+}
+
+/// Sets the permissions of `path`: the mode on Unix, the read-only attribute on Windows.
+/// Fields that don't apply to the current platform are ignored.
+#[command]
+async fn set_permissions<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: SetPermissionsOptions,
+) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode))?;
+    }
+
+    #[cfg(windows)]
+    if let Some(readonly) = options.readonly {
+        let mut permissions = std::fs::metadata(&path)?.permissions();
+        permissions.set_readonly(readonly);
+        std::fs::set_permissions(&path, permissions)?;
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    let _ = options;
+
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum SymlinkKind {
+    File,
+    Dir,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct SymlinkOptions {
+    /// Whether to create a file or a directory symlink. Only used on Windows,
+    /// where it defaults to the type of the target, or a file symlink if the target doesn't exist.
+    #[cfg_attr(not(windows), allow(dead_code))]
+    kind: Option<SymlinkKind>,
+}
+
+/// Creates a symlink at `link` pointing to `target`. A relative `target` is resolved against the directory of `link`.
+#[command]
+async fn symlink<R: Runtime>(
+    app: AppHandle<R>,
+    target: PathBuf,
+    link: PathBuf,
+    options: Option<SymlinkOptions>,
+) -> Result<()> {
+    ensure_allowed(&app, &link)?;
+    let resolved_target = match link.parent() {
+        Some(parent) if target.is_relative() => parent.join(&target),
+        _ => target.clone(),
+    };
+    ensure_allowed(&app, &resolved_target)?;
+
+    #[cfg(unix)]
+    let result = {
+        let _ = options;
+        std::os::unix::fs::symlink(&target, &link)
+    };
+    #[cfg(windows)]
+    let result = {
+        let kind = options.unwrap_or_default().kind.unwrap_or_else(|| {
+            if resolved_target.is_dir() {
+                SymlinkKind::Dir
+            } else {
+                SymlinkKind::File
+            }
+        });
+        match kind {
+            SymlinkKind::File => std::os::windows::fs::symlink_file(&target, &link),
+            SymlinkKind::Dir => std::os::windows::fs::symlink_dir(&target, &link),
+        }
+    };
+    #[cfg(not(any(unix, windows)))]
+    let result = {
+        let _ = options;
+        Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+    };
+
+    result.map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => Error::SymlinkPermissionDenied(link),
+        std::io::ErrorKind::Unsupported => Error::SymlinkUnsupported,
+        _ => e.into(),
+    })
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ReadTextFileOptions {
+    /// The label of the encoding, e.g. `utf-8`, `utf-16le`, `utf-16be` or `latin1`, defaults to UTF-8.
+    /// `auto` uses the encoding indicated by a BOM, or UTF-8 if there is none.
+    encoding: Option<String>,
+    /// Whether to read the target if `path` is a symlink, rather than failing. Defaults to `true`.
+    follow_symlinks: Option<bool>,
+}
+
+/// Reads a text file in the given encoding, failing on bytes that are invalid in that encoding.
+#[command]
+async fn read_text_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<ReadTextFileOptions>,
+) -> Result<String> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+    let bytes = read_limited(&app, &path, options.follow_symlinks.unwrap_or(true))?;
+
+    let encoding = resolve_encoding(options.encoding.as_deref(), &bytes)?;
+    decode_strict(&path, encoding, &bytes, 0, true)
+}
+
+/// Returns the encoding with the given label, where `auto` uses the encoding indicated by
+/// the BOM `head` starts with. Defaults to UTF-8.
+fn resolve_encoding(label: Option<&str>, head: &[u8]) -> Result<&'static Encoding> {
+    match label {
+        None => Ok(UTF_8),
+        Some("auto") => Ok(Encoding::for_bom(head).map_or(UTF_8, |(encoding, _)| encoding)),
+        Some(label) => Encoding::for_label(label.as_bytes())
+            .ok_or_else(|| Error::UnknownEncoding(label.into())),
+    }
+}
+
+/// Decodes `bytes` read from `path` at `offset`, failing on bytes that are invalid in the encoding.
+fn decode_strict(
+    path: &Path,
+    encoding: &'static Encoding,
+    bytes: &[u8],
+    offset: u64,
+    remove_bom: bool,
+) -> Result<String> {
+    // only removes a BOM of the same encoding
+    let mut decoder = if remove_bom {
+        encoding.new_decoder_with_bom_removal()
+    } else {
+        encoding.new_decoder_without_bom_handling()
+    };
+    let mut text = String::with_capacity(
+        decoder
+            .max_utf8_buffer_length_without_replacement(bytes.len())
+            .unwrap_or_default(),
+    );
+    let (result, read) = decoder.decode_to_string_without_replacement(bytes, &mut text, true);
+    match result {
+        DecoderResult::InputEmpty => Ok(text),
+        DecoderResult::Malformed(invalid, consumed) => {
+            let end = read - consumed as usize;
+            let start = end - invalid as usize;
+            Err(Error::Decode {
+                path: path.to_path_buf(),
+                encoding: encoding.name(),
+                offset: offset as usize + start,
+                bytes: bytes[start..end].to_vec(),
+            })
+        }
+        // the capacity is large enough for the whole input
+        DecoderResult::OutputFull => unreachable!(),
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ReadTextLinesOptions {
+    /// The label of the encoding, see [`ReadTextFileOptions::encoding`].
+    encoding: Option<String>,
+    /// The byte offset to start reading at, which should be the start of a line.
+    offset: u64,
+    /// The maximum number of lines to read, all remaining lines if not set.
+    max_lines: Option<usize>,
+    /// Whether to read the target if `path` is a symlink, rather than failing. Defaults to `true`.
+    follow_symlinks: Option<bool>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TextLines {
+    /// Each line without its line ending, or the error decoding it.
+    lines: Vec<std::result::Result<String, String>>,
+    /// The byte offset after the last line read, to continue reading from.
+    next_offset: u64,
+}
+
+/// Reads the bytes of a line up to and including `newline`, which is a code unit of the encoding
+/// so UTF-16 lines aren't split on a byte that is only half of a character.
+///
+/// At most `limit` bytes are read, so a huge line without a newline can't exhaust the memory.
+fn read_line_bytes(
+    reader: &mut impl BufRead,
+    newline: &[u8],
+    limit: u64,
+) -> std::io::Result<Vec<u8>> {
+    let mut reader = reader.take(limit);
+    let mut line = Vec::new();
+    if newline.len() == 1 {
+        reader.read_until(newline[0], &mut line)?;
+        return Ok(line);
+    }
+
+    let mut unit = vec![0; newline.len()];
+    loop {
+        let n = match reader.read(&mut unit) {
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let n = if n > 0 && n < unit.len() {
+            // a unit may be split across reads, a trailing partial unit is kept as is
+            match reader.read_exact(&mut unit[n..]) {
+                Ok(()) => unit.len(),
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => n,
+                Err(e) => return Err(e),
+            }
+        } else {
+            n
+        };
+        line.extend_from_slice(&unit[..n]);
+        if n < unit.len() || unit == newline {
+            return Ok(line);
+        }
+    }
+}
+
+/// Reads the lines of a text file in the given encoding, starting at `offset`.
+///
+/// Lines are decoded separately, so a line with bytes that are invalid in the encoding
+/// is returned as an error without failing the others. Paired with `next_offset`,
+/// this allows paging through large files, e.g. to follow a log file.
+#[command]
+async fn read_text_lines<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<ReadTextLinesOptions>,
+) -> Result<TextLines> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+    let mut file = open_file(&path, options.follow_symlinks.unwrap_or(true))?;
+
+    let encoding = if options.encoding.as_deref() == Some("auto") {
+        let mut head = Vec::new();
+        (&mut file).take(3).read_to_end(&mut head)?;
+        resolve_encoding(Some("auto"), &head)?
+    } else {
+        resolve_encoding(options.encoding.as_deref(), &[])?
+    };
+    let (newline, carriage_return): (&[u8], &[u8]) = if encoding == UTF_16LE {
+        (&[b'\n', 0], &[b'\r', 0])
+    } else if encoding == UTF_16BE {
+        (&[0, b'\n'], &[0, b'\r'])
+    } else {
+        (b"\n", b"\r")
+    };
+
+    file.seek(SeekFrom::Start(options.offset))?;
+    let mut reader = BufReader::new(file);
+    let max_read_bytes = app.state::<Config>().max_read_bytes;
+    let mut lines = Vec::new();
+    let mut offset = options.offset;
+    while options.max_lines.map_or(true, |max| lines.len() < max) {
+        // one byte past the remaining budget, to tell a line that is too long from one that fits
+        let remaining = max_read_bytes.map(|max| max - (offset - options.offset));
+        let limit = remaining.map_or(u64::MAX, |remaining| remaining.saturating_add(1));
+        let bytes = read_line_bytes(&mut reader, newline, limit)?;
+        if bytes.is_empty() {
+            break;
+        }
+        if let (Some(max), Some(remaining)) = (max_read_bytes, remaining) {
+            if bytes.len() as u64 > remaining {
+                return Err(Error::TooLarge(path, max));
+            }
+        }
+
+        let mut line = &bytes[..];
+        if let Some(stripped) = line.strip_suffix(newline) {
+            line = stripped.strip_suffix(carriage_return).unwrap_or(stripped);
+        }
+        lines.push(
+            decode_strict(&path, encoding, line, offset, offset == 0).map_err(|e| e.to_string()),
+        );
+        offset += bytes.len() as u64;
+    }
+
+    Ok(TextLines {
+        lines,
+        next_offset: offset,
+    })
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct GlobOptions {
+    case_insensitive: bool,
+}
+
+/// Returns the paths below `path` matching `pattern`, which is matched against the whole
+/// path relative to `path` rather than just the file name. Paths outside of the scope are skipped.
+#[command]
+async fn glob<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    pattern: String,
+    options: Option<GlobOptions>,
+) -> Result<Vec<PathBuf>> {
+    let options = options.unwrap_or_default();
+    let matcher = GlobBuilder::new(&pattern)
+        .case_insensitive(options.case_insensitive)
+        .literal_separator(true)
+        .build()?
+        .compile_matcher();
+
+    Ok(walk_dir(&app, &path)?
+        .into_iter()
+        .filter(|entry| matcher.is_match(&entry.path))
+        .map(|entry| path.join(entry.path))
+        .collect())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskUsage {
+    total_bytes: u64,
+    free_bytes: u64,
+    /// The free bytes available to the current user, which can be less than `free_bytes`.
+    available_bytes: u64,
+}
+
+/// Returns the space of the file system containing `path`. If `path` doesn't exist,
+/// the file system of its nearest existing ancestor is used, e.g. to check the space before creating a file.
+#[command]
+async fn disk_usage<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<DiskUsage> {
+    ensure_allowed(&app, &path)?;
+
+    let existing = path.ancestors().find(|p| p.exists()).unwrap_or(&path);
+    let stats = fs2::statvfs(existing)?;
+    Ok(DiskUsage {
+        total_bytes: stats.total_space(),
+        free_bytes: stats.free_space(),
+        available_bytes: stats.available_space(),
+    })
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct MkdirOptions {
+    /// Whether to create the missing parent directories as well.
+    recursive: bool,
+    /// The mode of the created directories. Only used on Unix.
+    #[cfg_attr(not(unix), allow(dead_code))]
+    mode: Option<u32>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MkdirResult {
+    /// `false` if the directory already existed.
+    created: bool,
+}
+
+/// Creates the directory at `path`, succeeding if it already exists.
+#[command]
+async fn mkdir<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<MkdirOptions>,
+) -> Result<MkdirResult> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+
+    let mut builder = DirBuilder::new();
+    #[cfg(unix)]
+    if let Some(mode) = options.mode {
+        builder.mode(mode);
+    }
+
+    if options.recursive {
+        if let Some(parent) = path.parent() {
+            builder.recursive(true).create(parent)?;
+            builder.recursive(false);
+        }
+    }
+
+    // creating the directory itself, rather than checking whether it exists first,
+    // tells reliably whether this call created it
+    match builder.create(&path) {
+        Ok(()) => Ok(MkdirResult { created: true }),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && path.is_dir() => {
+            Ok(MkdirResult { created: false })
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RemoveOptions {
+    /// Whether to remove directories with their contents.
+    recursive: bool,
+    /// Whether to move the path to the trash instead of deleting it permanently.
+    trash: bool,
+}
+
+/// Removes the file or directory at `path`, permanently unless `trash` is set.
+#[command]
+async fn remove<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    options: Option<RemoveOptions>,
+) -> Result<()> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+
+    if options.trash {
+        #[cfg(any(windows, target_os = "macos", target_os = "linux"))]
+        return Ok(trash::delete(&path)?);
+        #[cfg(not(any(windows, target_os = "macos", target_os = "linux")))]
+        return Err(Error::TrashUnsupported);
+    }
+
+    let metadata = std::fs::symlink_metadata(&path)?;
+    if !metadata.is_dir() {
+        std::fs::remove_file(&path)?;
+    } else if options.recursive {
+        std::fs::remove_dir_all(&path)?;
+    } else {
+        std::fs::remove_dir(&path)?;
+    }
+    Ok(())
+}
+
+/// Recursively copies `from` to `to` along with the permissions and timestamps of the copied entries.
+/// Symlinks are copied as symlinks.
+fn copy_preserving(from: &Path, to: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::symlink_metadata(from)?;
+
+    if metadata.file_type().is_symlink() {
+        let target = std::fs::read_link(from)?;
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(target, to);
+        #[cfg(windows)]
+        return if from.is_dir() {
+            std::os::windows::fs::symlink_dir(target, to)
+        } else {
+            std::os::windows::fs::symlink_file(target, to)
+        };
+        #[cfg(not(any(unix, windows)))]
+        return {
+            let _ = target;
+            Err(std::io::Error::from(std::io::ErrorKind::Unsupported))
+        };
+    }
+
+    if metadata.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_preserving(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        // after copying the entries, since the permissions may make the directory read-only
+        std::fs::set_permissions(to, metadata.permissions())?;
+    } else {
+        std::fs::copy(from, to)?;
+    }
+
+    filetime::set_file_times(
+        to,
+        FileTime::from_last_access_time(&metadata),
+        FileTime::from_last_modification_time(&metadata),
+    )
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct RenameOptions {
+    /// Whether to fail rather than copying and deleting `from_path` when it can't be renamed
+    /// because the destination is on another file system.
+    atomic: bool,
+}
+
+/// Finishes moving `from` to `to` after [`std::fs::rename`] failed with `error`.
+///
+/// Moves across file systems are copied along with their metadata to a temporary location
+/// next to `to`, renamed over it and deleted afterwards, unless `atomic` is set.
+/// A failed copy leaves `to` untouched. Any other error is returned as is.
+fn rename_fallback(from: &Path, to: &Path, error: std::io::Error, atomic: bool) -> Result<()> {
+    #[cfg(any(unix, windows))]
+    if error.raw_os_error() == Some(CROSSES_DEVICES_OS_ERROR) {
+        if atomic {
+            return Err(Error::CrossesDevices(from.to_path_buf()));
+        }
+
+        let dir = match to.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        // the temporary directory is removed along with a partial copy when it is dropped
+        let tmp_dir = tempfile::Builder::new().prefix(".tmp").tempdir_in(dir)?;
+        let tmp_path = tmp_dir.path().join("copy");
+        copy_preserving(from, &tmp_path)?;
+        std::fs::rename(&tmp_path, to)?;
+
+        if std::fs::symlink_metadata(from)?.is_dir() {
+            std::fs::remove_dir_all(from)?;
+        } else {
+            std::fs::remove_file(from)?;
+        }
+        return Ok(());
+    }
+
+    Err(error.into())
+}
+
+/// Moves `from_path` to `to_path`.
+///
+/// Moves across file systems, which can't be done with a rename, fall back to copying
+/// `from_path` along with its metadata and deleting it afterwards, unless `atomic` is set.
+#[command]
+async fn rename<R: Runtime>(
+    app: AppHandle<R>,
+    from_path: PathBuf,
+    to_path: PathBuf,
+    options: Option<RenameOptions>,
+) -> Result<()> {
+    ensure_allowed(&app, &from_path)?;
+    ensure_allowed(&app, &to_path)?;
+    let options = options.unwrap_or_default();
+
+    std::fs::rename(&from_path, &to_path)
+        .or_else(|e| rename_fallback(&from_path, &to_path, e, options.atomic))
+}
+
+/// Returns the absolute path of `path` with all symlinks and `.` and `..` components resolved.
+///
+/// The path must exist. The resolved path is checked against the scope as well, so symlinks can't escape it.
+#[command]
+async fn canonicalize<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<PathBuf> {
+    ensure_allowed(&app, &path)?;
+    let canonical = std::fs::canonicalize(&path)?;
+    ensure_allowed(&app, &canonical)?;
+    Ok(canonical)
+}
+
+/// How the file system scope treated a path.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(feature = "debug-scope"), allow(dead_code))]
+enum ScopeVerdict {
+    Allowed,
+    Denied,
+    NotMatched,
+}
+
+/// The scope diagnostics of a path, see [`debug_scope`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(feature = "debug-scope"), allow(dead_code))]
+struct ScopeDebug {
+    /// The path the scope patterns are matched against, canonicalized if it exists.
+    resolved_path: PathBuf,
+    allowed_patterns: Vec<String>,
+    forbidden_patterns: Vec<String>,
+    matched_allowed: Vec<String>,
+    matched_forbidden: Vec<String>,
+    verdict: ScopeVerdict,
+}
+
+/// Explains how the file system scope treats `path`, to debug why a path is forbidden.
+///
+/// Only the path and the scope patterns are returned, the file is never read.
+/// Requires the `debug-scope` feature.
+#[cfg(feature = "debug-scope")]
+#[command]
+async fn debug_scope<R: Runtime>(app: AppHandle<R>, path: PathBuf) -> Result<ScopeDebug> {
+    let scope = app.fs_scope();
+
+    // the options the scope matches with, which it doesn't expose
+    let require_literal_leading_dot = match &app.config().tauri.allowlist.fs.scope {
+        tauri::utils::config::FsAllowlistScope::Scope {
+            require_literal_leading_dot: Some(require),
+            ..
+        } => *require,
+        // dotfiles are hidden by default on Unix
+        _ => cfg!(unix),
+    };
+    let match_options = glob::MatchOptions {
+        require_literal_separator: true,
+        require_literal_leading_dot,
+        ..Default::default()
+    };
+
+    // resolve the path the same way the scope does before matching it
+    let resolved_path: PathBuf = if path.exists() {
+        std::fs::canonicalize(&path)?
+    } else {
+        path
+    }
+    .components()
+    .collect();
+
+    let patterns = |patterns: HashSet<tauri::scope::GlobPattern>| {
+        let mut patterns = patterns
+            .iter()
+            .map(|p| p.as_str().to_string())
+            .collect::<Vec<_>>();
+        patterns.sort();
+        patterns
+    };
+    let matching = |patterns: HashSet<tauri::scope::GlobPattern>| {
+        let mut patterns = patterns
+            .iter()
+            .filter(|p| p.matches_path_with(&resolved_path, match_options))
+            .map(|p| p.as_str().to_string())
+            .collect::<Vec<_>>();
+        patterns.sort();
+        patterns
+    };
+
+    let matched_forbidden = matching(scope.forbidden_patterns());
+    let verdict = if scope.is_allowed(&resolved_path) {
+        ScopeVerdict::Allowed
+    } else if matched_forbidden.is_empty() {
+        ScopeVerdict::NotMatched
+    } else {
+        ScopeVerdict::Denied
+    };
+
+    Ok(ScopeDebug {
+        allowed_patterns: patterns(scope.allowed_patterns()),
+        forbidden_patterns: patterns(scope.forbidden_patterns()),
+        matched_allowed: matching(scope.allowed_patterns()),
+        matched_forbidden,
+        verdict,
+        resolved_path,
+    })
+}
+
+#[cfg(not(feature = "debug-scope"))]
+#[command]
+async fn debug_scope(_path: PathBuf) -> Result<ScopeDebug> {
+    Err(Error::ScopeDebugDisabled)
+}
+
+/// Lexically resolves the `.` and `..` components of `path`, without touching the file system.
+fn normalize_lexically(path: &Path) -> Vec<Component<'_>> {
+    let mut components = Vec::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match components.last() {
+                Some(Component::Normal(_)) => {
+                    components.pop();
+                }
+                // `..` can't go above the root
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => components.push(component),
+            },
+            _ => components.push(component),
+        }
+    }
+    components
+}
+
+/// Returns the path of `target` relative to `base`, computed purely lexically so it works
+/// for paths that don't exist yet. Returns `None` if no relative path exists,
+/// e.g. when the paths are on different Windows drives.
+fn lexical_relative_path(base: &Path, target: &Path) -> Option<PathBuf> {
+    if base.has_root() != target.has_root() {
+        return None;
+    }
+
+    let base = normalize_lexically(base);
+    let target = normalize_lexically(target);
+
+    let prefix = |components: &[Component]| match components.first() {
+        Some(Component::Prefix(prefix)) => Some(prefix.as_os_str().to_ascii_lowercase()),
+        _ => None,
+    };
+    if prefix(&base) != prefix(&target) {
+        return None;
+    }
+
+    let common = base
+        .iter()
+        .zip(&target)
+        .take_while(|(a, b)| match (a, b) {
+            (Component::Prefix(_), Component::Prefix(_)) => true,
+            _ => a == b,
+        })
+        .count();
+
+    // a relative base going above its starting point can't be walked back
+    if base[common..].contains(&Component::ParentDir) {
+        return None;
+    }
+
+    let mut relative: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    relative.extend(&target[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(Component::CurDir);
+    }
+
+    Some(relative)
+}
+
+/// Returns the path of `target` relative to `base`, see [`lexical_relative_path`].
+#[command]
+async fn relative_path<R: Runtime>(
+    app: AppHandle<R>,
+    base: PathBuf,
+    target: PathBuf,
+) -> Result<Option<PathBuf>> {
+    ensure_allowed(&app, &base)?;
+    ensure_allowed(&app, &target)?;
+    Ok(lexical_relative_path(&base, &target))
+}
+
+enum Hasher {
+    Sha256(Sha256),
+    #[cfg(feature = "extra-hashes")]
+    Sha1(sha1::Sha1),
+    #[cfg(feature = "extra-hashes")]
+    Md5(md5::Md5),
+    #[cfg(feature = "extra-hashes")]
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: &str) -> Result<Self> {
+        match algorithm {
+            "Sha256" => Ok(Self::Sha256(Sha256::new())),
+            #[cfg(feature = "extra-hashes")]
+            "Sha1" => Ok(Self::Sha1(sha1::Sha1::new())),
+            #[cfg(feature = "extra-hashes")]
+            "Md5" => Ok(Self::Md5(md5::Md5::new())),
+            #[cfg(feature = "extra-hashes")]
+            "Blake3" => Ok(Self::Blake3(Default::default())),
+            #[cfg(not(feature = "extra-hashes"))]
+            "Sha1" | "Md5" | "Blake3" => Err(Error::HashAlgorithmDisabled(algorithm.into())),
+            _ => Err(Error::UnknownHashAlgorithm(algorithm.into())),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            #[cfg(feature = "extra-hashes")]
+            Self::Sha1(hasher) => hasher.update(data),
+            #[cfg(feature = "extra-hashes")]
+            Self::Md5(hasher) => hasher.update(data),
+            #[cfg(feature = "extra-hashes")]
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// Returns the lowercase hex digest.
+    fn finalize(self) -> String {
+        match self {
+            Self::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            #[cfg(feature = "extra-hashes")]
+            Self::Sha1(hasher) => format!("{:x}", hasher.finalize()),
+            #[cfg(feature = "extra-hashes")]
+            Self::Md5(hasher) => format!("{:x}", hasher.finalize()),
+            #[cfg(feature = "extra-hashes")]
+            Self::Blake3(hasher) => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct HashFileOptions {
+    /// Whether to hash the target if `path` is a symlink, rather than failing. Defaults to `true`.
+    follow_symlinks: Option<bool>,
+}
+
+/// Hashes the file with the given algorithm, streaming it through the hasher
+/// so it never has to be held in memory. Returns the hex digest.
+fn hash(path: &Path, algorithm: &str, follow_symlinks: bool) -> Result<String> {
+    let mut hasher = Hasher::new(algorithm)?;
+
+    let mut file = open_file(path, follow_symlinks)?;
+    let mut buf = vec![0; DEFAULT_CHUNK_SIZE];
+    loop {
+        let n = match file.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        hasher.update(&buf[..n]);
+    }
+
+    Ok(hasher.finalize())
+}
+
+/// Hashes the file with the given algorithm. Returns the hex digest.
+///
+/// `Sha256` is always available, `Sha1`, `Md5` and `Blake3` require the `extra-hashes` feature.
+#[command]
+async fn hash_file<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    algorithm: String,
+    options: Option<HashFileOptions>,
+) -> Result<String> {
+    ensure_allowed(&app, &path)?;
+    let options = options.unwrap_or_default();
+    hash(&path, &algorithm, options.follow_symlinks.unwrap_or(true))
+}
+
+#[derive(Default)]
+pub struct Builder {
+    max_read_bytes: Option<u64>,
+    structured_errors: bool,
+}
+
+impl Builder {
+    /// Sets the maximum number of bytes the commands reading a whole file, or a range of it, into memory may read.
+    ///
+    /// Reading a larger file fails instead of exhausting the memory. Streaming commands are not limited.
+    pub fn max_read_bytes(mut self, max: u64) -> Self {
+        self.max_read_bytes = Some(max);
+        self
+    }
+
+    /// Rejects the commands with a `{ kind, message }` object instead of the error message.
+    ///
+    /// Disabled by default to keep the string errors existing frontends expect.
+    pub fn structured_errors(mut self, enabled: bool) -> Self {
+        self.structured_errors = enabled;
+        self
+    }
+
+    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
+        PluginBuilder::new("fs-extra")
+            .invoke_handler(tauri::generate_handler![
+                exists,
+                metadata,
+                rotate_file,
+                write_verify,
+                increment_file,
+                read_file_stream,
+                read_dir,
+                read_dir_recursive,
+                read_text_file_detect,
+                glob,
+                relative_path,
+                hash_file,
+                copy_dir,
+                copy_file,
+                symlink,
+                set_permissions,
+                set_times,
+                read_range,
+                append_file,
+                append_text_file,
+                create_temp,
+                fsync,
+                fdatasync,
+                read_text_file,
+                read_text_lines,
+                canonicalize,
+                debug_scope,
+                stat_many,
+                open_write_stream,
+                write_chunk,
+                close_write_stream,
+                disk_usage,
+                write_file_atomic,
+                remove,
+                rename,
+                mkdir
+            ])
+            .setup(move |app| {
+                STRUCTURED_ERRORS.store(self.structured_errors, Ordering::Relaxed);
+                app.manage(Config {
+                    max_read_bytes: self.max_read_bytes,
+                });
+                app.manage(WriteStreams::default());
+                Ok(())
+            })
+            .build()
+    }
+}
+
+pub fn init<R: Runtime>() -> TauriPlugin<R> {
+    Builder::default().build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_path_walks_up_to_the_common_ancestor() {
+        assert_eq!(
+            lexical_relative_path(Path::new("/a/b/c"), Path::new("/a/d/e.txt")),
+            Some(PathBuf::from("../../d/e.txt"))
+        );
+        assert_eq!(
+            lexical_relative_path(Path::new("/a/b"), Path::new("/a/b/c")),
+            Some(PathBuf::from("c"))
+        );
+        assert_eq!(
+            lexical_relative_path(Path::new("/a/b"), Path::new("/a/b")),
+            Some(PathBuf::from("."))
+        );
+    }
+
+    #[test]
+    fn relative_path_resolves_dot_components_lexically() {
+        assert_eq!(
+            lexical_relative_path(Path::new("/a/./b/../c"), Path::new("/a/c/d")),
+            Some(PathBuf::from("d"))
+        );
+        assert_eq!(
+            lexical_relative_path(Path::new("/.."), Path::new("/a")),
+            Some(PathBuf::from("a"))
+        );
+    }
+
+    #[test]
+    fn relative_path_requires_comparable_paths() {
+        assert_eq!(lexical_relative_path(Path::new("/a"), Path::new("b")), None);
+        // a base above its starting point can't be walked back
+        assert_eq!(
+            lexical_relative_path(Path::new("../a"), Path::new("b")),
+            None
+        );
+        assert_eq!(
+            lexical_relative_path(Path::new("a/b"), Path::new("a/c")),
+            Some(PathBuf::from("../c"))
+        );
+    }
+
+    #[test]
+    fn copy_dir_copies_nested_entries_and_reports_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir_all(from.join("a/b")).unwrap();
+        std::fs::create_dir_all(from.join("empty")).unwrap();
+        std::fs::write(from.join("root.txt"), b"12345").unwrap();
+        std::fs::write(from.join("a/b/nested.txt"), b"123").unwrap();
+
+        let to = dir.path().join("to");
+        let mut progress = Vec::new();
+        let copied = copy_dir_filtered(&from, &to, false, |_| true, |p| progress.push(p)).unwrap();
+
+        assert_eq!(copied, 8);
+        assert_eq!(std::fs::read(to.join("root.txt")).unwrap(), b"12345");
+        assert_eq!(std::fs::read(to.join("a/b/nested.txt")).unwrap(), b"123");
+        assert!(to.join("empty").is_dir());
+        assert_eq!(progress.len(), 2);
+        assert!(progress.iter().all(|p| p.total_bytes == 8));
+        assert_eq!(progress.last().unwrap().copied_bytes, 8);
+    }
+
+    #[test]
+    fn copy_dir_skips_entries_that_are_not_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir_all(from.join("secret")).unwrap();
+        std::fs::write(from.join("secret/key"), b"key").unwrap();
+        std::fs::write(from.join("public.txt"), b"public").unwrap();
+
+        let to = dir.path().join("to");
+        let is_allowed = |path: &Path| !path.ends_with("secret");
+        copy_dir_filtered(&from, &to, false, is_allowed, |_| {}).unwrap();
+
+        assert!(to.join("public.txt").is_file());
+        assert!(!to.join("secret").exists());
+    }
+
+    #[test]
+    fn copy_dir_only_overwrites_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir_all(&from).unwrap();
+        std::fs::write(from.join("file.txt"), b"new").unwrap();
+        let to = dir.path().join("to");
+        std::fs::create_dir_all(&to).unwrap();
+        std::fs::write(to.join("file.txt"), b"old").unwrap();
+
+        assert!(matches!(
+            copy_dir_filtered(&from, &to, false, |_| true, |_| {}),
+            Err(Error::AlreadyExists(_))
+        ));
+        assert_eq!(std::fs::read(to.join("file.txt")).unwrap(), b"old");
+
+        copy_dir_filtered(&from, &to, true, |_| true, |_| {}).unwrap();
+        assert_eq!(std::fs::read(to.join("file.txt")).unwrap(), b"new");
+    }
+
+    #[test]
+    fn copy_dir_fails_if_the_destination_is_not_allowed() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir_all(&from).unwrap();
+        let to = dir.path().join("to");
+
+        assert!(matches!(
+            copy_dir_filtered(&from, &to, false, |path| path != to, |_| {}),
+            Err(Error::PathForbidden(_))
+        ));
+        assert!(!to.exists());
+    }
+
+    #[test]
+    fn read_to_end_limited_allows_files_up_to_the_maximum() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"12345").unwrap();
+
+        let read = |max| read_to_end_limited(File::open(&path).unwrap(), &path, max);
+        assert_eq!(read(None).unwrap(), b"12345");
+        assert_eq!(read(Some(5)).unwrap(), b"12345");
+        assert!(matches!(read(Some(4)), Err(Error::TooLarge(_, 4))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_to_end_limited_stops_reading_files_without_a_length() {
+        let path = Path::new("/dev/zero");
+        assert!(matches!(
+            read_to_end_limited(File::open(path).unwrap(), path, Some(1024)),
+            Err(Error::TooLarge(_, 1024))
+        ));
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_without_leaving_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn write_atomic_keeps_the_permissions_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        std::fs::write(&path, b"first").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+
+        write_atomic(&path, b"second").unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o640);
+    }
+
+    #[test]
+    fn read_line_bytes_splits_on_the_newline() {
+        let mut reader = &b"first\r\nsecond\nlast"[..];
+        assert_eq!(
+            read_line_bytes(&mut reader, b"\n", u64::MAX).unwrap(),
+            b"first\r\n"
+        );
+        assert_eq!(
+            read_line_bytes(&mut reader, b"\n", u64::MAX).unwrap(),
+            b"second\n"
+        );
+        assert_eq!(
+            read_line_bytes(&mut reader, b"\n", u64::MAX).unwrap(),
+            b"last"
+        );
+        assert!(read_line_bytes(&mut reader, b"\n", u64::MAX)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn read_line_bytes_splits_utf16_on_whole_code_units() {
+        // "\u{a00}" is encoded as [0x00, 0x0a] and must not be mistaken for a newline
+        let mut reader = &[0x00, 0x0a, 0x0a, 0x00, 0x41, 0x00][..];
+        assert_eq!(
+            read_line_bytes(&mut reader, &[0x0a, 0x00], u64::MAX).unwrap(),
+            [0x00, 0x0a, 0x0a, 0x00]
+        );
+        assert_eq!(
+            read_line_bytes(&mut reader, &[0x0a, 0x00], u64::MAX).unwrap(),
+            [0x41, 0x00]
+        );
+    }
+
+    #[test]
+    fn read_line_bytes_stops_at_the_limit() {
+        let mut reader = &b"a very long line\nnext\n"[..];
+        assert_eq!(read_line_bytes(&mut reader, b"\n", 6).unwrap(), b"a very");
+
+        let mut reader = &[0x41, 0x00, 0x42, 0x00, 0x0a, 0x00][..];
+        assert_eq!(
+            read_line_bytes(&mut reader, &[0x0a, 0x00], 2).unwrap(),
+            [0x41, 0x00]
+        );
+    }
+
+    #[test]
+    fn copy_preserving_keeps_the_timestamps_and_permissions() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir_all(from.join("nested")).unwrap();
+        std::fs::write(from.join("nested/file"), b"content").unwrap();
+        let mtime = FileTime::from_unix_time(1_000_000_000, 0);
+        filetime::set_file_mtime(from.join("nested/file"), mtime).unwrap();
+        filetime::set_file_mtime(from.join("nested"), mtime).unwrap();
+        let mut permissions = std::fs::metadata(from.join("nested/file"))
+            .unwrap()
+            .permissions();
+        permissions.set_readonly(true);
+        std::fs::set_permissions(from.join("nested/file"), permissions).unwrap();
+
+        let to = dir.path().join("to");
+        copy_preserving(&from, &to).unwrap();
+
+        let file = std::fs::metadata(to.join("nested/file")).unwrap();
+        assert_eq!(std::fs::read(to.join("nested/file")).unwrap(), b"content");
+        assert!(file.permissions().readonly());
+        assert_eq!(FileTime::from_last_modification_time(&file), mtime);
+        let nested = std::fs::metadata(to.join("nested")).unwrap();
+        assert_eq!(FileTime::from_last_modification_time(&nested), mtime);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn copy_preserving_copies_symlinks_as_symlinks() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        std::fs::create_dir(&from).unwrap();
+        std::os::unix::fs::symlink("missing-target", from.join("link")).unwrap();
+
+        let to = dir.path().join("to");
+        copy_preserving(&from, &to).unwrap();
+
+        assert_eq!(
+            std::fs::read_link(to.join("link")).unwrap(),
+            Path::new("missing-target")
+        );
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn rename_fallback_copies_across_devices() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        let to = dir.path().join("to");
+        std::fs::create_dir_all(from.join("nested")).unwrap();
+        std::fs::write(from.join("nested/file"), b"content").unwrap();
+
+        let error = std::io::Error::from_raw_os_error(CROSSES_DEVICES_OS_ERROR);
+        rename_fallback(&from, &to, error, false).unwrap();
+
+        assert!(!from.exists());
+        assert_eq!(std::fs::read(to.join("nested/file")).unwrap(), b"content");
+        let mut files = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, ["to"]);
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn rename_fallback_keeps_the_target_if_the_copy_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let to = dir.path().join("to");
+        std::fs::write(&to, b"previous").unwrap();
+
+        let error = std::io::Error::from_raw_os_error(CROSSES_DEVICES_OS_ERROR);
+        assert!(rename_fallback(&dir.path().join("missing"), &to, error, false).is_err());
+
+        assert_eq!(std::fs::read(&to).unwrap(), b"previous");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[cfg(any(unix, windows))]
+    #[test]
+    fn rename_fallback_fails_atomic_moves_and_other_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        let to = dir.path().join("to");
+        std::fs::write(&from, b"content").unwrap();
+
+        let error = std::io::Error::from_raw_os_error(CROSSES_DEVICES_OS_ERROR);
+        assert!(matches!(
+            rename_fallback(&from, &to, error, true),
+            Err(Error::CrossesDevices(path)) if path == from
+        ));
+
+        let error = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            rename_fallback(&from, &to, error, false),
+            Err(Error::Io(_))
+        ));
+
+        assert!(from.exists());
+        assert!(!to.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hash_rejects_symlinks_unless_they_are_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        let link = dir.path().join("link");
+        std::fs::write(&file, b"content").unwrap();
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+
+        assert_eq!(
+            hash(&link, "Sha256", true).unwrap(),
+            hash(&file, "Sha256", false).unwrap()
+        );
+        assert!(matches!(
+            hash(&link, "Sha256", false),
+            Err(Error::SymlinkNotFollowed(path)) if path == link
+        ));
+        assert!(matches!(
+            open_file(&link, false),
+            Err(Error::SymlinkNotFollowed(path)) if path == link
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn walk_dir_skips_entries_it_cannot_follow() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("nested")).unwrap();
+        std::fs::write(dir.path().join("nested/file"), b"content").unwrap();
+        std::os::unix::fs::symlink("missing-target", dir.path().join("nested/dangling")).unwrap();
+        std::os::unix::fs::symlink("..", dir.path().join("nested/cycle")).unwrap();
+
+        let mut entries = walk_dir_filtered(dir.path(), |_| true)
+            .unwrap()
+            .into_iter()
+            .map(|entry| (entry.path, entry.is_dir, entry.is_symlink))
+            .collect::<Vec<_>>();
+        entries.sort();
+        assert_eq!(
+            entries,
+            [
+                (PathBuf::from("nested"), true, false),
+                (PathBuf::from("nested/cycle"), true, true),
+                (PathBuf::from("nested/dangling"), false, true),
+                (PathBuf::from("nested/file"), false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn walk_dir_fails_if_the_root_cannot_be_read() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(walk_dir_filtered(&dir.path().join("missing"), |_| true).is_err());
+    }
+
+    #[test]
+    fn increment_updates_the_counter_in_place() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+
+        assert_eq!(increment(&path, 1).unwrap(), 1);
+        std::fs::write(&path, "100\n").unwrap();
+        assert_eq!(increment(&path, -1).unwrap(), 99);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "99");
+        assert_eq!(increment(&path, 1).unwrap(), 100);
+
+        // no lock file is left behind
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn increment_rejects_invalid_counters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter");
+        std::fs::write(&path, "abc").unwrap();
+        assert!(matches!(increment(&path, 1), Err(Error::InvalidCounter(_))));
+        std::fs::write(&path, i64::MAX.to_string()).unwrap();
+        assert!(matches!(
+            increment(&path, 1),
+            Err(Error::CounterOverflow(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            i64::MAX.to_string()
+        );
+    }
 }
@@ -6,6 +6,14 @@ const w: WebviewWindow = appWindow;
 
 export interface WatchOptions {
   recursive?: boolean;
+  /**
+   * Glob patterns of paths, relative to the watched paths, to ignore events for.
+   * Everything below a matching directory is ignored as well, e.g. `["node_modules", ".git"]`.
+   *
+   * Ignored paths are still watched, only their events are dropped, so ignoring a large
+   * directory doesn't reduce the number of OS watches used for it.
+   */
+  ignore?: string[];
 }
 
 export interface DebouncedWatchOptions extends WatchOptions {
@@ -14,6 +22,13 @@ export interface DebouncedWatchOptions extends WatchOptions {
 
 export type RawEvent = {
   type: RawEventKind;
+  /**
+   * A simplified kind of the event. Renames are reported as `rename` rather than `modify`.
+   *
+   * Only set for the events of {@link watchImmediate}, debounced events only carry the
+   * `Any` or `AnyContinuous` kind of {@link DebouncedEvent}.
+   */
+  kind?: "create" | "modify" | "rename" | "remove" | "access" | "other";
   paths: string[];
   attrs: unknown;
 };
@@ -117,3 +132,25 @@ export async function watchImmediate(
     unlisten();
   };
 }
+
+export interface WatchInfo {
+  id: number;
+  paths: string[];
+}
+
+/**
+ * Lists the active watchers and the paths they watch.
+ */
+export async function listWatches(): Promise<WatchInfo[]> {
+  return await invoke("plugin:fs-watch|list_watches");
+}
+
+/**
+ * Stops watching `path` in all watchers watching it.
+ * Watchers left without any watched path are stopped.
+ *
+ * @returns The ids of the watchers that were watching `path`.
+ */
+export async function unwatchPath(path: string): Promise<number[]> {
+  return await invoke("plugin:fs-watch|unwatch_path", { path });
+}
@@ -1,4 +1,8 @@
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use notify::{
+    event::{EventKind, ModifyKind},
+    Config, Event, RecommendedWatcher, RecursiveMode, Watcher,
+};
 use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
 use serde::{ser::Serializer, Deserialize, Serialize};
 use tauri::{
@@ -9,7 +13,7 @@ use tauri::{
 
 use std::{
     collections::HashMap,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{
         mpsc::{channel, Receiver},
         Mutex,
@@ -25,6 +29,8 @@ type Id = u32;
 pub enum Error {
     #[error(transparent)]
     Watch(#[from] notify::Error),
+    #[error(transparent)]
+    Glob(#[from] globset::Error),
 }
 
 impl Serialize for Error {
@@ -44,23 +50,114 @@ enum WatcherKind {
     Watcher(RecommendedWatcher),
 }
 
-fn watch_raw<R: Runtime>(window: Window<R>, rx: Receiver<notify::Result<Event>>, id: Id) {
+impl WatcherKind {
+    fn unwatch(&mut self, path: &Path) -> notify::Result<()> {
+        match self {
+            Self::Debouncer(debouncer) => debouncer.watcher().unwatch(path),
+            Self::Watcher(watcher) => watcher.unwatch(path),
+        }
+    }
+}
+
+/// Matches paths against the ignore patterns, relative to the watched paths.
+struct IgnoreMatcher {
+    roots: Vec<PathBuf>,
+    globs: GlobSet,
+}
+
+impl IgnoreMatcher {
+    fn new(roots: Vec<PathBuf>, patterns: &[String]) -> Result<Self> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in patterns {
+            builder.add(Glob::new(pattern)?);
+        }
+        Ok(Self {
+            roots,
+            globs: builder.build()?,
+        })
+    }
+
+    /// A path is ignored if it, or any of its parents below a watched path, matches a pattern.
+    fn is_ignored(&self, path: &Path) -> bool {
+        if self.globs.is_empty() {
+            return false;
+        }
+        self.roots
+            .iter()
+            .filter_map(|root| path.strip_prefix(root).ok())
+            .any(|relative| {
+                relative
+                    .ancestors()
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .any(|p| self.globs.is_match(p))
+            })
+    }
+}
+
+/// A raw event, along with a simplified kind the frontend can branch on.
+///
+/// Only raw watches get this kind, the debouncer doesn't keep the kind of the underlying events
+/// so debounced events are emitted with its own `Any` or `AnyContinuous` kind instead.
+#[derive(Clone, Serialize)]
+struct RawEvent {
+    #[serde(flatten)]
+    event: Event,
+    kind: &'static str,
+}
+
+impl From<Event> for RawEvent {
+    fn from(event: Event) -> Self {
+        let kind = match event.kind {
+            EventKind::Create(_) => "create",
+            EventKind::Modify(ModifyKind::Name(_)) => "rename",
+            EventKind::Modify(_) => "modify",
+            EventKind::Remove(_) => "remove",
+            EventKind::Access(_) => "access",
+            EventKind::Any | EventKind::Other => "other",
+        };
+        Self { event, kind }
+    }
+}
+
+/// Emits the events of the watcher until it is dropped, which closes the channel and ends the thread.
+fn watch_raw<R: Runtime>(
+    window: Window<R>,
+    rx: Receiver<notify::Result<Event>>,
+    id: Id,
+    ignore: IgnoreMatcher,
+) {
     spawn(move || {
         let event_name = format!("watcher://raw-event/{id}");
         while let Ok(event) = rx.recv() {
-            if let Ok(event) = event {
+            if let Ok(mut event) = event {
+                if !event.paths.is_empty() {
+                    event.paths.retain(|p| !ignore.is_ignored(p));
+                    if event.paths.is_empty() {
+                        continue;
+                    }
+                }
                 // TODO: Should errors be emitted too?
-                let _ = window.emit(&event_name, event);
+                let _ = window.emit(&event_name, RawEvent::from(event));
             }
         }
     });
 }
 
-fn watch_debounced<R: Runtime>(window: Window<R>, rx: Receiver<DebounceEventResult>, id: Id) {
+/// Emits the events of the debouncer until it is dropped, which closes the channel and ends the thread.
+fn watch_debounced<R: Runtime>(
+    window: Window<R>,
+    rx: Receiver<DebounceEventResult>,
+    id: Id,
+    ignore: IgnoreMatcher,
+) {
     spawn(move || {
         let event_name = format!("watcher://debounced-event/{id}");
         while let Ok(event) = rx.recv() {
-            if let Ok(event) = event {
+            if let Ok(mut event) = event {
+                event.retain(|e| !ignore.is_ignored(&e.path));
+                if event.is_empty() {
+                    continue;
+                }
                 // TODO: Should errors be emitted too?
                 let _ = window.emit(&event_name, event);
             }
@@ -73,6 +170,12 @@ fn watch_debounced<R: Runtime>(window: Window<R>, rx: Receiver<DebounceEventResu
 struct WatchOptions {
     delay_ms: Option<u64>,
     recursive: bool,
+    /// Glob patterns of paths, relative to the watched paths, whose events are not emitted.
+    ///
+    /// Ignored paths are still watched, only their events are dropped, so ignoring a large
+    /// directory doesn't reduce the number of OS watches used for it.
+    #[serde(default)]
+    ignore: Vec<String>,
 }
 
 #[command]
@@ -88,6 +191,7 @@ async fn watch<R: Runtime>(
     } else {
         RecursiveMode::NonRecursive
     };
+    let ignore = IgnoreMatcher::new(paths.clone(), &options.ignore)?;
 
     let watcher = if let Some(delay) = options.delay_ms {
         let (tx, rx) = channel();
@@ -96,7 +200,7 @@ async fn watch<R: Runtime>(
         for path in &paths {
             watcher.watch(path, mode)?;
         }
-        watch_debounced(window, rx, id);
+        watch_debounced(window, rx, id, ignore);
         WatcherKind::Debouncer(debouncer)
     } else {
         let (tx, rx) = channel();
@@ -104,7 +208,7 @@ async fn watch<R: Runtime>(
         for path in &paths {
             watcher.watch(path, mode)?;
         }
-        watch_raw(window, rx, id);
+        watch_raw(window, rx, id, ignore);
         WatcherKind::Watcher(watcher)
     };
 
@@ -115,29 +219,114 @@ async fn watch<R: Runtime>(
 
 #[command]
 async fn unwatch(watchers: State<'_, WatcherCollection>, id: Id) -> Result<()> {
-    if let Some((watcher, paths)) = watchers.0.lock().unwrap().remove(&id) {
-        match watcher {
-            WatcherKind::Debouncer(mut debouncer) => {
-                for path in paths {
-                    debouncer.watcher().unwatch(&path)?
-                }
-            }
-            WatcherKind::Watcher(mut watcher) => {
-                for path in paths {
-                    watcher.unwatch(&path)?
-                }
-            }
-        };
+    if let Some((mut watcher, paths)) = watchers.0.lock().unwrap().remove(&id) {
+        for path in paths {
+            watcher.unwatch(&path)?
+        }
     }
     Ok(())
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchInfo {
+    id: Id,
+    paths: Vec<PathBuf>,
+}
+
+/// Lists the active watchers and the paths they watch.
+#[command]
+async fn list_watches(watchers: State<'_, WatcherCollection>) -> Result<Vec<WatchInfo>> {
+    Ok(watchers
+        .0
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, (_, paths))| WatchInfo {
+            id: *id,
+            paths: paths.clone(),
+        })
+        .collect())
+}
+
+/// Stops watching `path` in all watchers watching it. Watchers left without paths are dropped,
+/// which stops their event threads. Returns the ids of the watchers that were watching `path`.
+#[command]
+async fn unwatch_path(watchers: State<'_, WatcherCollection>, path: PathBuf) -> Result<Vec<Id>> {
+    let mut watchers = watchers.0.lock().unwrap();
+    let mut ids = Vec::new();
+    let mut result = Ok(());
+    for (id, (watcher, paths)) in watchers.iter_mut() {
+        if let Some(index) = paths.iter().position(|p| p == &path) {
+            // forget the path even if unwatching it fails, e.g. because it was removed,
+            // so it isn't listed as watched anymore and its watcher can be dropped
+            paths.remove(index);
+            ids.push(*id);
+            if let Err(e) = watcher.unwatch(&path) {
+                result = result.and(Err(e));
+            }
+        }
+    }
+    watchers.retain(|_, (_, paths)| !paths.is_empty());
+    result?;
+    Ok(ids)
+}
+
 pub fn init<R: Runtime>() -> TauriPlugin<R> {
     PluginBuilder::new("fs-watch")
-        .invoke_handler(tauri::generate_handler![watch, unwatch])
+        .invoke_handler(tauri::generate_handler![
+            watch,
+            unwatch,
+            list_watches,
+            unwatch_path
+        ])
         .setup(|app| {
             app.manage(WatcherCollection::default());
             Ok(())
         })
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matcher(patterns: &[&str]) -> IgnoreMatcher {
+        IgnoreMatcher::new(
+            vec![PathBuf::from("/project")],
+            &patterns.iter().map(|p| p.to_string()).collect::<Vec<_>>(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ignores_paths_below_matching_directories() {
+        let ignore = matcher(&["node_modules", ".git"]);
+        assert!(ignore.is_ignored(Path::new("/project/node_modules")));
+        assert!(ignore.is_ignored(Path::new("/project/node_modules/pkg/index.js")));
+        assert!(ignore.is_ignored(Path::new("/project/.git/HEAD")));
+        assert!(!ignore.is_ignored(Path::new("/project/src/main.rs")));
+    }
+
+    #[test]
+    fn matches_patterns_relative_to_the_watched_path() {
+        let ignore = matcher(&["*.log", "target/**"]);
+        assert!(ignore.is_ignored(Path::new("/project/app.log")));
+        assert!(ignore.is_ignored(Path::new("/project/logs/app.log")));
+        assert!(ignore.is_ignored(Path::new("/project/target/debug/app")));
+        assert!(!ignore.is_ignored(Path::new("/project/src/target.rs")));
+        // paths outside of the watched paths are never ignored
+        assert!(!ignore.is_ignored(Path::new("/other/app.log")));
+    }
+
+    #[test]
+    fn ignores_nothing_without_patterns() {
+        let ignore = matcher(&[]);
+        assert!(!ignore.is_ignored(Path::new("/project/node_modules")));
+    }
+
+    #[test]
+    fn rejects_invalid_patterns() {
+        assert!(IgnoreMatcher::new(Vec::new(), &["a[".to_string()]).is_err());
+    }
+}
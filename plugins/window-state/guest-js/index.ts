@@ -1,3 +1,4 @@
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
 import { invoke } from "@tauri-apps/api/tauri";
 import { WindowLabel, getCurrent } from "@tauri-apps/api/window";
 
@@ -8,22 +9,50 @@ export enum StateFlags {
   VISIBLE = 1 << 3,
   DECORATIONS = 1 << 4,
   FULLSCREEN = 1 << 5,
-  ALL = SIZE | POSITION | MAXIMIZED | VISIBLE | DECORATIONS | FULLSCREEN,
+  MINIMIZED = 1 << 6,
+  MONITOR = 1 << 7,
+  ALL = SIZE |
+    POSITION |
+    MAXIMIZED |
+    VISIBLE |
+    DECORATIONS |
+    FULLSCREEN |
+    MINIMIZED |
+    MONITOR,
 }
 
 /**
  *  Save the state of all open windows to disk.
+ *
+ *  If `flags` is omitted, the flags configured on the plugin builder are used.
  */
-async function saveWindowState(flags: StateFlags): Promise<void> {
+async function saveWindowState(flags?: StateFlags): Promise<void> {
   return invoke("plugin:window-state|save_window_state", { flags });
 }
 
+/**
+ *  Save the state of all open windows to the given path instead of the configured location,
+ *  e.g. to export a snapshot of the window layout.
+ *
+ *  If `flags` is omitted, the flags configured on the plugin builder are used.
+ *
+ *  @returns The number of bytes written.
+ */
+async function saveWindowStateTo(
+  path: string,
+  flags?: StateFlags,
+): Promise<number> {
+  return invoke("plugin:window-state|save_window_state_to", { path, flags });
+}
+
 /**
  *  Restore the state for the specified window from disk.
+ *
+ *  If `flags` is omitted, the flags configured on the plugin builder are used.
  */
 async function restoreState(
   label: WindowLabel,
-  flags: StateFlags,
+  flags?: StateFlags,
 ): Promise<void> {
   return invoke("plugin:window-state|restore_state", { label, flags });
 }
@@ -31,8 +60,69 @@ async function restoreState(
 /**
  *  Restore the state for the current window from disk.
  */
-async function restoreStateCurrent(flags: StateFlags): Promise<void> {
+async function restoreStateCurrent(flags?: StateFlags): Promise<void> {
   return restoreState(getCurrent().label, flags);
 }
 
-export { restoreState, restoreStateCurrent, saveWindowState };
+/**
+ *  Remove the saved state of the specified window from disk,
+ *  so it is restored to its default state on the next launch.
+ */
+async function removeWindowState(label: WindowLabel): Promise<void> {
+  return invoke("plugin:window-state|remove_window_state", { label });
+}
+
+/**
+ *  Remove the saved state of all windows from disk.
+ */
+async function clearAllWindowStates(): Promise<void> {
+  return invoke("plugin:window-state|clear_all_window_states");
+}
+
+export interface SavedMonitor {
+  name: string | null;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface WindowState {
+  width: number;
+  height: number;
+  x: number;
+  y: number;
+  prevX: number;
+  prevY: number;
+  maximized: boolean;
+  visible: boolean;
+  decorated: boolean;
+  fullscreen: boolean;
+  minimized: boolean;
+  monitor: SavedMonitor | null;
+  scaleFactor: number;
+}
+
+/**
+ *  Listen to changes of the state of all windows.
+ *
+ *  Requires the plugin to be built with `Builder::with_state_events(true)`.
+ */
+async function onStateChange(
+  handler: (state: Record<WindowLabel, WindowState>) => void
+): Promise<UnlistenFn> {
+  return listen<Record<WindowLabel, WindowState>>(
+    "window-state://change",
+    (event) => handler(event.payload)
+  );
+}
+
+export {
+  restoreState,
+  restoreStateCurrent,
+  saveWindowState,
+  saveWindowStateTo,
+  removeWindowState,
+  clearAllWindowStates,
+  onStateChange,
+};
@@ -1,28 +1,72 @@
-use crate::{AppHandleExt, StateFlags, WindowExt};
+use crate::{save_cached_states, save_cached_states_to, PluginState, StateFlags, WindowExt};
+use std::path::PathBuf;
 use tauri::{command, AppHandle, Manager, Runtime};
 
+/// Parses the flags given to a command, `None` uses the flags configured on the plugin builder.
+fn resolve_flags(flags: Option<u32>) -> std::result::Result<Option<StateFlags>, String> {
+    flags
+        .map(|flags| {
+            StateFlags::from_bits(flags)
+                .ok_or_else(|| format!("Invalid state flags bits: {}", flags))
+        })
+        .transpose()
+}
+
 #[command]
 pub async fn save_window_state<R: Runtime>(
     app: AppHandle<R>,
-    flags: u32,
+    flags: Option<u32>,
 ) -> std::result::Result<(), String> {
-    let flags = StateFlags::from_bits(flags)
-        .ok_or_else(|| format!("Invalid state flags bits: {}", flags))?;
-    app.save_window_state(flags).map_err(|e| e.to_string())?;
+    let flags = resolve_flags(flags)?;
+    save_cached_states(&app, flags, false).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+#[command]
+pub async fn save_window_state_to<R: Runtime>(
+    app: AppHandle<R>,
+    path: PathBuf,
+    flags: Option<u32>,
+) -> std::result::Result<u64, String> {
+    if !app.fs_scope().is_allowed(&path) {
+        return Err(format!(
+            "path not allowed on the configured scope: {}",
+            path.display()
+        ));
+    }
+    let flags = resolve_flags(flags)?;
+    save_cached_states_to(&app, &path, flags).map_err(|e| e.to_string())
+}
+
 #[command]
 pub async fn restore_state<R: Runtime>(
     app: AppHandle<R>,
     label: String,
-    flags: u32,
+    flags: Option<u32>,
 ) -> std::result::Result<(), String> {
-    let flags = StateFlags::from_bits(flags)
-        .ok_or_else(|| format!("Invalid state flags bits: {}", flags))?;
+    let flags = app
+        .state::<PluginState>()
+        .flags_for(&label, resolve_flags(flags)?);
     app.get_window(&label)
         .ok_or_else(|| format!("Couldn't find window with label: {}", label))?
         .restore_state(flags)
         .map_err(|e| e.to_string())?;
     Ok(())
 }
+
+#[command]
+pub async fn remove_window_state<R: Runtime>(
+    app: AppHandle<R>,
+    label: String,
+) -> std::result::Result<(), String> {
+    app.remove_window_state(&label).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+#[command]
+pub async fn clear_all_window_states<R: Runtime>(
+    app: AppHandle<R>,
+) -> std::result::Result<(), String> {
+    app.clear_all_window_states().map_err(|e| e.to_string())?;
+    Ok(())
+}
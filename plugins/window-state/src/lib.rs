@@ -3,24 +3,44 @@
 // SPDX-License-Identifier: MIT
 
 use bitflags::bitflags;
+use chacha20poly1305::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
 use serde::{Deserialize, Serialize};
 use tauri::{
     plugin::{Builder as PluginBuilder, TauriPlugin},
     LogicalSize, Manager, Monitor, PhysicalPosition, PhysicalSize, RunEvent, Runtime, Window,
     WindowEvent,
 };
+use tempfile::NamedTempFile;
 
 use std::{
     collections::{HashMap, HashSet},
-    fs::{create_dir_all, File},
-    io::Write,
-    sync::{Arc, Mutex},
+    fs::create_dir_all,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
 };
 
 mod cmd;
 
 pub const STATE_FILENAME: &str = ".window-state";
 
+/// The event emitted with the state of all windows when enabled with [`Builder::with_state_events`].
+pub const STATE_CHANGE_EVENT: &str = "window-state://change";
+
+/// The minimum interval between saves triggered by windows losing focus.
+const FOCUS_LOST_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Fraction of the monitor size used for maximized windows without a saved size.
+const UNMAXIMIZED_MONITOR_RATIO: f64 = 0.75;
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
@@ -31,6 +51,18 @@ pub enum Error {
     TauriApi(#[from] tauri::api::Error),
     #[error(transparent)]
     Bincode(#[from] Box<bincode::ErrorKind>),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    TomlSerialize(#[from] toml::ser::Error),
+    #[error(transparent)]
+    TomlDeserialize(#[from] toml::de::Error),
+    /// An error returned by a custom [`StateStore`].
+    #[error(transparent)]
+    Store(Box<dyn std::error::Error + Send + Sync>),
+    /// The state could not be encrypted, or decrypted with the key set with [`Builder::with_encryption`].
+    #[error("failed to encrypt or decrypt the window state")]
+    Encryption,
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -44,16 +76,173 @@ bitflags! {
         const VISIBLE     = 1 << 3;
         const DECORATIONS = 1 << 4;
         const FULLSCREEN  = 1 << 5;
+        const MINIMIZED   = 1 << 6;
+        const MONITOR     = 1 << 7;
     }
 }
 
 impl Default for StateFlags {
     fn default() -> Self {
-        Self::all()
+        // restoring relative to the saved monitor is opt-in
+        Self::all() - Self::MONITOR
+    }
+}
+
+/// The geometry a window is about to be restored to, see [`Builder::on_restore_geometry`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestoreGeometry {
+    /// The outer position of the window.
+    pub position: PhysicalPosition<i32>,
+    /// The size of the window, see [`Builder::with_size_kind`].
+    pub size: LogicalSize<f64>,
+}
+
+impl RestoreGeometry {
+    /// The geometry a window that was maximized before its size was ever saved unmaximizes to:
+    /// a fraction of the monitor size, centered on the monitor.
+    fn unmaximized_on(monitor: MonitorBounds) -> Self {
+        let size = monitor.size.to_logical::<f64>(monitor.scale_factor);
+        let mut geometry = Self {
+            position: monitor.position,
+            size: LogicalSize {
+                width: size.width * UNMAXIMIZED_MONITOR_RATIO,
+                height: size.height * UNMAXIMIZED_MONITOR_RATIO,
+            },
+        };
+        geometry.center_on(monitor);
+        geometry
+    }
+
+    /// Shrinks the size to fit on the monitor, preserving its aspect ratio.
+    fn fit_size_to(&mut self, monitor: MonitorBounds) {
+        let monitor_size = monitor.size.to_logical::<f64>(monitor.scale_factor);
+        let ratio =
+            (monitor_size.width / self.size.width).min(monitor_size.height / self.size.height);
+        if ratio < 1. {
+            self.size.width *= ratio;
+            self.size.height *= ratio;
+        }
+    }
+
+    /// Moves the geometry to the center of the monitor.
+    fn center_on(&mut self, monitor: MonitorBounds) {
+        let size = self.size.to_physical::<i32>(monitor.scale_factor);
+        let PhysicalPosition { x, y } = monitor.position;
+        let PhysicalSize { width, height } = monitor.size;
+        self.position.x = x + (width as i32 - size.width) / 2;
+        self.position.y = y + (height as i32 - size.height) / 2;
+    }
+
+    /// Shrinks the geometry to fit on the monitor and moves it fully onto it.
+    fn clamp_to(&mut self, monitor: MonitorBounds) {
+        let scale_factor = monitor.scale_factor;
+        let monitor_size = monitor.size.to_logical::<f64>(scale_factor);
+        self.size.width = self.size.width.min(monitor_size.width).max(1.);
+        self.size.height = self.size.height.min(monitor_size.height).max(1.);
+
+        let size = self.size.to_physical::<i32>(scale_factor);
+        let PhysicalPosition { x, y } = monitor.position;
+        let PhysicalSize { width, height } = monitor.size;
+        self.position.x = self.position.x.min(x + width as i32 - size.width).max(x);
+        self.position.y = self.position.y.min(y + height as i32 - size.height).max(y);
+    }
+}
+
+/// The bounds of a monitor, so the geometry math doesn't depend on a [`Monitor`] handle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct MonitorBounds {
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+    scale_factor: f64,
+}
+
+impl From<&Monitor> for MonitorBounds {
+    fn from(monitor: &Monitor) -> Self {
+        Self {
+            position: *monitor.position(),
+            size: *monitor.size(),
+            scale_factor: monitor.scale_factor(),
+        }
+    }
+}
+
+impl MonitorBounds {
+    /// Whether any corner of the window at `position` with `size` is on the monitor.
+    fn intersects(&self, position: PhysicalPosition<i32>, size: LogicalSize<u32>) -> bool {
+        let size = size.to_physical::<u32>(self.scale_factor);
+
+        let PhysicalPosition { x, y } = self.position;
+        let PhysicalSize { width, height } = self.size;
+
+        let left = x;
+        let right = x + width as i32;
+        let top = y;
+        let bottom = y + height as i32;
+
+        [
+            (position.x, position.y),
+            (position.x + size.width as i32, position.y),
+            (position.x, position.y + size.height as i32),
+            (
+                position.x + size.width as i32,
+                position.y + size.height as i32,
+            ),
+        ]
+        .into_iter()
+        .any(|(x, y)| x >= left && x < right && y >= top && y < bottom)
     }
+
+    /// Scales the offset of `position` on the monitor from `scale_factor` to the monitor's own,
+    /// so a physical position saved on a monitor with a different scale factor lands where
+    /// it visually was. Positions saved without a scale factor are returned as is.
+    fn rescale(&self, position: PhysicalPosition<i32>, scale_factor: f64) -> PhysicalPosition<i32> {
+        if scale_factor <= 0. || scale_factor == self.scale_factor {
+            return position;
+        }
+        let ratio = self.scale_factor / scale_factor;
+        PhysicalPosition {
+            x: self.position.x + ((position.x - self.position.x) as f64 * ratio).round() as i32,
+            y: self.position.y + ((position.y - self.position.y) as f64 * ratio).round() as i32,
+        }
+    }
+
+    /// Moves `position` down so the title bar is reachable if it is above the monitor.
+    fn clamp_title_bar(&self, mut position: PhysicalPosition<i32>) -> PhysicalPosition<i32> {
+        position.y = position.y.max(self.position.y);
+        position
+    }
+}
+
+type OnRestoreGeometry = Arc<dyn Fn(&str, &Monitor, &mut RestoreGeometry) + Send + Sync>;
+
+/// Which size of a window is saved and restored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SizeKind {
+    /// The size of the window's client area, excluding decorations.
+    #[default]
+    Inner,
+    /// The size of the entire window, including decorations.
+    Outer,
 }
 
-#[derive(Debug, Deserialize, Serialize, PartialEq)]
+/// How to restore windows whose saved position isn't on any of the available monitors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OffscreenFallback {
+    /// Leave the window where the OS places it.
+    #[default]
+    LeaveToOs,
+    /// Center the window on the primary monitor, shrinking it to fit if needed.
+    Center,
+}
+
+/// The saved state of a window.
+///
+/// `#[serde(default)]` only lets the self-describing formats load files missing newer fields.
+/// Bincode files are decoded by position, so adding a field requires bumping [`STATE_VERSION`]
+/// and decoding the files of older versions with a struct frozen at their layout,
+/// like [`WindowStateV2`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
 struct WindowState {
     width: f64,
     height: f64,
@@ -68,6 +257,126 @@ struct WindowState {
     visible: bool,
     decorated: bool,
     fullscreen: bool,
+    minimized: bool,
+    monitor: Option<SavedMonitor>,
+    /// The scale factor of the monitor the position was saved on, `0` if unknown.
+    scale_factor: f64,
+}
+
+/// The state of a window as exposed outside of the plugin, e.g. by [`AppHandleExt::window_state`],
+/// to a [`StateStore`] and in the [`STATE_CHANGE_EVENT`] payload.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WindowStateSnapshot {
+    /// The logical width of the window.
+    pub width: f64,
+    /// The logical height of the window.
+    pub height: f64,
+    /// The physical x coordinate of the window's outer position.
+    pub x: i32,
+    /// The physical y coordinate of the window's outer position.
+    pub y: i32,
+    /// The x coordinate before the window was maximized, which moves it to the corner of the monitor.
+    pub prev_x: i32,
+    /// The y coordinate before the window was maximized.
+    pub prev_y: i32,
+    pub maximized: bool,
+    pub visible: bool,
+    pub decorated: bool,
+    pub fullscreen: bool,
+    pub minimized: bool,
+    /// The monitor the window was on, if tracked with [`StateFlags::MONITOR`].
+    pub monitor: Option<SavedMonitor>,
+    /// The scale factor of the monitor the position was captured on, `0` if unknown.
+    pub scale_factor: f64,
+}
+
+impl From<&WindowState> for WindowStateSnapshot {
+    fn from(state: &WindowState) -> Self {
+        Self {
+            width: state.width,
+            height: state.height,
+            x: state.x,
+            y: state.y,
+            prev_x: state.prev_x,
+            prev_y: state.prev_y,
+            maximized: state.maximized,
+            visible: state.visible,
+            decorated: state.decorated,
+            fullscreen: state.fullscreen,
+            minimized: state.minimized,
+            monitor: state.monitor.clone(),
+            scale_factor: state.scale_factor,
+        }
+    }
+}
+
+impl From<WindowStateSnapshot> for WindowState {
+    fn from(snapshot: WindowStateSnapshot) -> Self {
+        Self {
+            width: snapshot.width,
+            height: snapshot.height,
+            x: snapshot.x,
+            y: snapshot.y,
+            prev_x: snapshot.prev_x,
+            prev_y: snapshot.prev_y,
+            maximized: snapshot.maximized,
+            visible: snapshot.visible,
+            decorated: snapshot.decorated,
+            fullscreen: snapshot.fullscreen,
+            minimized: snapshot.minimized,
+            monitor: snapshot.monitor,
+            scale_factor: snapshot.scale_factor,
+        }
+    }
+}
+
+/// Converts the window states to their snapshots, keeping the labels.
+fn snapshots(state: &HashMap<String, WindowState>) -> HashMap<String, WindowStateSnapshot> {
+    state.iter().map(|(k, v)| (k.clone(), v.into())).collect()
+}
+
+/// Identifies the monitor a window was on when its state was saved.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct SavedMonitor {
+    pub name: Option<String>,
+    /// The physical position and size of the monitor.
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl SavedMonitor {
+    fn new(monitor: &Monitor) -> Self {
+        let PhysicalPosition { x, y } = *monitor.position();
+        let PhysicalSize { width, height } = *monitor.size();
+        Self {
+            name: monitor.name().cloned(),
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Finds this monitor by name, preferring the one with the same size and position
+    /// if several share the name. Monitors may have moved since they were saved
+    /// because they were reconnected in a different order.
+    fn find<'a>(&self, monitors: &'a [Monitor]) -> Option<&'a Monitor> {
+        let name = self.name.as_ref()?;
+        monitors
+            .iter()
+            .filter(|m| m.name() == Some(name))
+            .max_by_key(|m| {
+                let PhysicalPosition { x, y } = *m.position();
+                let PhysicalSize { width, height } = *m.size();
+                (
+                    width == self.width && height == self.height,
+                    x == self.x && y == self.y,
+                )
+            })
+    }
 }
 
 impl Default for WindowState {
@@ -83,56 +392,703 @@ impl Default for WindowState {
             visible: true,
             decorated: true,
             fullscreen: Default::default(),
+            minimized: Default::default(),
+            monitor: Default::default(),
+            scale_factor: Default::default(),
+        }
+    }
+}
+
+impl WindowState {
+    /// Whether all the fields tracked by `flags` are at their default values,
+    /// untracked fields are ignored.
+    fn is_default(&self, flags: StateFlags) -> bool {
+        let default = Self::default();
+
+        [
+            (
+                StateFlags::SIZE,
+                self.width == default.width && self.height == default.height,
+            ),
+            (
+                StateFlags::POSITION,
+                self.x == default.x
+                    && self.y == default.y
+                    && self.prev_x == default.prev_x
+                    && self.prev_y == default.prev_y,
+            ),
+            (StateFlags::MAXIMIZED, self.maximized == default.maximized),
+            (StateFlags::VISIBLE, self.visible == default.visible),
+            (StateFlags::DECORATIONS, self.decorated == default.decorated),
+            (
+                StateFlags::FULLSCREEN,
+                self.fullscreen == default.fullscreen,
+            ),
+            (StateFlags::MINIMIZED, self.minimized == default.minimized),
+            (StateFlags::MONITOR, self.monitor == default.monitor),
+        ]
+        .into_iter()
+        .all(|(flag, eq)| !flags.contains(flag) || eq)
+    }
+}
+
+/// A storage backend the window states are loaded from and saved to.
+pub trait StateStore {
+    /// Loads the saved window states, keyed by window label.
+    fn load(&self) -> Result<HashMap<String, WindowStateSnapshot>>;
+    /// Saves the window states, keyed by window label.
+    fn save(&self, state: &HashMap<String, WindowStateSnapshot>) -> Result<()>;
+}
+
+/// The version of the schema the window states are saved with.
+/// Files saved before the schema was versioned are a bare map of the window states.
+///
+/// Version 3 added the scale factor, version 2 files are decoded with [`WindowStateV2`].
+const STATE_VERSION: u32 = 3;
+
+/// The prefix of versioned bincode files. Unversioned bincode files start with
+/// the length of the map instead, which can't match it in practice.
+const BINCODE_MAGIC: &[u8] = b"WNDSTATE";
+
+#[derive(Serialize)]
+struct VersionedStateRef<'a> {
+    version: u32,
+    windows: &'a HashMap<String, WindowState>,
+}
+
+#[derive(Deserialize)]
+struct VersionedState<T = WindowState> {
+    version: u32,
+    windows: HashMap<String, T>,
+}
+
+/// The layout of [`WindowState`] in version 2 bincode files, before the scale factor was added.
+#[derive(Deserialize)]
+struct WindowStateV2 {
+    width: f64,
+    height: f64,
+    x: i32,
+    y: i32,
+    prev_x: i32,
+    prev_y: i32,
+    maximized: bool,
+    visible: bool,
+    decorated: bool,
+    fullscreen: bool,
+    minimized: bool,
+    monitor: Option<SavedMonitor>,
+}
+
+impl From<WindowStateV2> for WindowState {
+    fn from(state: WindowStateV2) -> Self {
+        Self {
+            width: state.width,
+            height: state.height,
+            x: state.x,
+            y: state.y,
+            prev_x: state.prev_x,
+            prev_y: state.prev_y,
+            maximized: state.maximized,
+            visible: state.visible,
+            decorated: state.decorated,
+            fullscreen: state.fullscreen,
+            minimized: state.minimized,
+            monitor: state.monitor,
+            ..Default::default()
+        }
+    }
+}
+
+/// The layout of [`WindowState`] in unversioned files. Bincode isn't self-describing,
+/// so these can't be deserialized into [`WindowState`] with its fields added since.
+#[derive(Deserialize)]
+struct UnversionedWindowState {
+    width: f64,
+    height: f64,
+    x: i32,
+    y: i32,
+    prev_x: i32,
+    prev_y: i32,
+    maximized: bool,
+    visible: bool,
+    decorated: bool,
+    fullscreen: bool,
+}
+
+impl From<UnversionedWindowState> for WindowState {
+    fn from(state: UnversionedWindowState) -> Self {
+        Self {
+            width: state.width,
+            height: state.height,
+            x: state.x,
+            y: state.y,
+            prev_x: state.prev_x,
+            prev_y: state.prev_y,
+            maximized: state.maximized,
+            visible: state.visible,
+            decorated: state.decorated,
+            fullscreen: state.fullscreen,
+            ..Default::default()
+        }
+    }
+}
+
+/// The format the window states are saved with by [`FileStore`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateFormat {
+    /// Compact binary format.
+    #[default]
+    Bincode,
+    Json,
+    JsonPretty,
+    Toml,
+}
+
+impl StateFormat {
+    /// The state file name for this format, [`STATE_FILENAME`] with a matching extension.
+    pub fn filename(&self) -> String {
+        match self {
+            Self::Bincode => STATE_FILENAME.into(),
+            Self::Json | Self::JsonPretty => format!("{STATE_FILENAME}.json"),
+            Self::Toml => format!("{STATE_FILENAME}.toml"),
+        }
+    }
+
+    fn serialize(&self, state: &HashMap<String, WindowState>) -> Result<Vec<u8>> {
+        let state = VersionedStateRef {
+            version: STATE_VERSION,
+            windows: state,
+        };
+        Ok(match self {
+            Self::Bincode => [BINCODE_MAGIC, &bincode::serialize(&state)?].concat(),
+            Self::Json => serde_json::to_vec(&state)?,
+            Self::JsonPretty => serde_json::to_vec_pretty(&state)?,
+            Self::Toml => toml::to_string_pretty(&state)?.into_bytes(),
+        })
+    }
+
+    /// Deserializes versioned files, and upgrades unversioned ones which are saved versioned from then on.
+    fn deserialize(&self, state: &[u8]) -> Result<HashMap<String, WindowState>> {
+        let versioned = match self {
+            Self::Bincode => match state.strip_prefix(BINCODE_MAGIC) {
+                // the version comes first, so it tells which layout the windows are saved with
+                Some(state) => match bincode::deserialize::<u32>(state)? {
+                    2 => {
+                        let versioned =
+                            bincode::deserialize::<VersionedState<WindowStateV2>>(state)?;
+                        VersionedState {
+                            version: versioned.version,
+                            windows: versioned
+                                .windows
+                                .into_iter()
+                                .map(|(k, v)| (k, v.into()))
+                                .collect(),
+                        }
+                    }
+                    _ => bincode::deserialize::<VersionedState>(state)?,
+                },
+                None => {
+                    let state: HashMap<String, UnversionedWindowState> =
+                        bincode::deserialize(state)?;
+                    return Ok(state.into_iter().map(|(k, v)| (k, v.into())).collect());
+                }
+            },
+            Self::Json | Self::JsonPretty => {
+                match serde_json::from_slice::<VersionedState>(state) {
+                    Ok(versioned) => versioned,
+                    Err(_) => return Ok(serde_json::from_slice(state)?),
+                }
+            }
+            Self::Toml => {
+                let state = String::from_utf8_lossy(state);
+                match toml::from_str::<VersionedState>(&state) {
+                    Ok(versioned) => versioned,
+                    Err(_) => return Ok(toml::from_str(&state)?),
+                }
+            }
+        };
+
+        if versioned.version > STATE_VERSION {
+            log::warn!(
+                "window-state: the state was saved with a newer schema version ({}), fields unknown to this version are dropped",
+                versioned.version
+            );
+        }
+        Ok(versioned.windows)
+    }
+}
+
+/// The header gzip compressed data starts with.
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+fn compress(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(bytes)?;
+    Ok(encoder.finish()?)
+}
+
+/// Decompresses gzip compressed state, or returns the state as is if it isn't compressed.
+fn decompress(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !bytes.starts_with(GZIP_MAGIC) {
+        return Ok(bytes);
+    }
+    let mut decompressed = Vec::new();
+    GzDecoder::new(&bytes[..]).read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
+/// The header encrypted state starts with, followed by the encryption format version and the nonce.
+const ENCRYPTION_MAGIC: &[u8] = b"WSENC";
+const ENCRYPTION_VERSION: u8 = 1;
+const NONCE_LEN: usize = 12;
+
+/// Encrypts the state with ChaCha20-Poly1305 and a random nonce.
+fn encrypt(bytes: &[u8], key: &[u8; 32]) -> Result<Vec<u8>> {
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, bytes)
+        .map_err(|_| Error::Encryption)?;
+
+    let mut encrypted =
+        Vec::with_capacity(ENCRYPTION_MAGIC.len() + 1 + NONCE_LEN + ciphertext.len());
+    encrypted.extend_from_slice(ENCRYPTION_MAGIC);
+    encrypted.push(ENCRYPTION_VERSION);
+    encrypted.extend_from_slice(&nonce);
+    encrypted.extend_from_slice(&ciphertext);
+    Ok(encrypted)
+}
+
+/// Decrypts encrypted state, or returns the state as is if it isn't encrypted.
+fn decrypt(bytes: Vec<u8>, key: Option<&[u8; 32]>) -> Result<Vec<u8>> {
+    if !bytes.starts_with(ENCRYPTION_MAGIC) {
+        return Ok(bytes);
+    }
+    let key = key.ok_or(Error::Encryption)?;
+    let header_len = ENCRYPTION_MAGIC.len() + 1;
+    if bytes.len() < header_len + NONCE_LEN || bytes[ENCRYPTION_MAGIC.len()] != ENCRYPTION_VERSION {
+        return Err(Error::Encryption);
+    }
+    let (nonce, ciphertext) = bytes[header_len..].split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| Error::Encryption)
+}
+
+/// Compresses and encrypts the serialized state as configured.
+fn encode(bytes: Vec<u8>, compression: bool, encryption_key: Option<&[u8; 32]>) -> Result<Vec<u8>> {
+    let bytes = if compression {
+        compress(&bytes)?
+    } else {
+        bytes
+    };
+    match encryption_key {
+        Some(key) => encrypt(&bytes, key),
+        None => Ok(bytes),
+    }
+}
+
+/// The default [`StateStore`], saving the window states to a file.
+///
+/// The plugin uses [`STATE_FILENAME`] in the app config directory unless a custom store is set.
+pub struct FileStore {
+    path: PathBuf,
+    format: StateFormat,
+    compression: bool,
+    encryption_key: Option<[u8; 32]>,
+}
+
+impl FileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            format: Default::default(),
+            compression: false,
+            encryption_key: None,
+        }
+    }
+
+    /// Sets the format the window states are saved with, defaults to [`StateFormat::Bincode`].
+    pub fn with_format(mut self, format: StateFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Whether to gzip compress the state file, defaults to `false`.
+    ///
+    /// Both compressed and uncompressed files are loaded regardless of this setting.
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypts the state file with ChaCha20-Poly1305 using the given key.
+    ///
+    /// Unencrypted files are still loaded, and are encrypted on the next save.
+    pub fn with_encryption(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key.replace(key);
+        self
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&self) -> Result<HashMap<String, WindowStateSnapshot>> {
+        if self.path.exists() {
+            let state = decrypt(
+                tauri::api::file::read_binary(&self.path)?,
+                self.encryption_key.as_ref(),
+            )?;
+            let state = decompress(state)?;
+            Ok(snapshots(&self.format.deserialize(&state)?))
+        } else {
+            Ok(Default::default())
         }
     }
+
+    fn save(&self, state: &HashMap<String, WindowStateSnapshot>) -> Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        create_dir_all(dir)?;
+
+        let state = state
+            .iter()
+            .map(|(k, v)| (k.clone(), v.clone().into()))
+            .collect();
+        let bytes = encode(
+            self.format.serialize(&state)?,
+            self.compression,
+            self.encryption_key.as_ref(),
+        )?;
+
+        // write to a uniquely named temporary file next to the state file and move it into place
+        // once it is fully written, so a crash mid-write never leaves a corrupt state file behind
+        // and concurrent saves never write to the same temporary file.
+        // the rename replaces the existing file on all platforms, including Windows.
+        let mut file = NamedTempFile::new_in(dir)?;
+        file.write_all(&bytes)?;
+        file.as_file().sync_all()?;
+        file.persist(&self.path).map_err(|e| e.error)?;
+        Ok(())
+    }
 }
 
 struct WindowStateCache(Arc<Mutex<HashMap<String, WindowState>>>);
+
+/// Plugin options that need to be available outside of the plugin builder.
+#[derive(Clone, Default)]
+struct PluginState {
+    allowlist: HashSet<String>,
+    denylist: HashSet<String>,
+    clamp_position: bool,
+    clamp_size: bool,
+    size_kind: SizeKind,
+    state_flags: StateFlags,
+    window_flags: HashMap<String, StateFlags>,
+    store: Option<Arc<dyn StateStore + Send + Sync>>,
+    on_restore_geometry: Option<OnRestoreGeometry>,
+    format: StateFormat,
+    compression: bool,
+    encryption_key: Option<[u8; 32]>,
+    emit_state_changes: bool,
+    skip_never_shown: bool,
+    offscreen_fallback: OffscreenFallback,
+    /// The labels of the windows that have been visible at some point.
+    shown: Arc<Mutex<HashSet<String>>>,
+    save_on_focus_lost: bool,
+    last_focus_lost_save: Arc<Mutex<Option<Instant>>>,
+    /// Whether a save is scheduled with [`schedule_save`] and hasn't run yet.
+    save_scheduled: Arc<AtomicBool>,
+    /// Whether the scheduled save is for a window losing focus.
+    focus_lost_save_scheduled: Arc<AtomicBool>,
+    /// Whether the cached state changed since it was last written to the store.
+    dirty: Arc<AtomicBool>,
+    min_save_interval: Option<Duration>,
+    last_save: Arc<Mutex<Option<Instant>>>,
+}
+
+impl PluginState {
+    /// Returns `flags` if they were given explicitly, otherwise the flags set for the window
+    /// with [`Builder::with_window_flags`] or the ones set with [`Builder::with_state_flags`].
+    fn flags_for(&self, label: &str, flags: Option<StateFlags>) -> StateFlags {
+        flags.unwrap_or_else(|| {
+            self.window_flags
+                .get(label)
+                .copied()
+                .unwrap_or(self.state_flags)
+        })
+    }
+
+    fn save(&self, state: &HashMap<String, WindowState>) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.save(&snapshots(state))?;
+        }
+        self.dirty.store(false, Ordering::SeqCst);
+        self.last_save.lock().unwrap().replace(Instant::now());
+        Ok(())
+    }
+
+    /// Saves the state if it changed since the last write and, unless `force` is set,
+    /// the interval set with [`Builder::with_min_save_interval`] has passed since then.
+    ///
+    /// Returns the time left until the interval passes if the save was skipped because of it.
+    fn save_if_dirty(
+        &self,
+        state: &HashMap<String, WindowState>,
+        force: bool,
+    ) -> Result<Option<Duration>> {
+        if !self.dirty.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+        if !force {
+            if let (Some(interval), Some(last)) =
+                (self.min_save_interval, *self.last_save.lock().unwrap())
+            {
+                let elapsed = last.elapsed();
+                if elapsed < interval {
+                    return Ok(Some(interval - elapsed));
+                }
+            }
+        }
+        self.save(state).map(|_| None)
+    }
+
+    fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::SeqCst);
+    }
+
+    /// Records that the window has been shown, if it is visible.
+    fn mark_if_shown<R: Runtime>(&self, window: &Window<R>) {
+        if self.skip_never_shown && window.is_visible().unwrap_or(false) {
+            self.shown.lock().unwrap().insert(window.label().into());
+        }
+    }
+
+    /// Whether the window's geometry should be captured, which is not the case for windows that were never shown
+    /// if enabled with [`Builder::skip_never_shown`].
+    fn should_capture<R: Runtime>(&self, window: &Window<R>) -> bool {
+        self.mark_if_shown(window);
+        !self.skip_never_shown || self.shown.lock().unwrap().contains(window.label())
+    }
+
+    /// The delay before saving for a window losing focus, throttled to [`FOCUS_LOST_SAVE_INTERVAL`].
+    /// A zero delay means the save is due now, and it is recorded as the last one.
+    fn focus_lost_save_delay(&self) -> Duration {
+        let mut last_save = self.last_focus_lost_save.lock().unwrap();
+        let delay = last_save.map_or(Duration::ZERO, |last| {
+            FOCUS_LOST_SAVE_INTERVAL.saturating_sub(last.elapsed())
+        });
+        if delay.is_zero() {
+            last_save.replace(Instant::now());
+        }
+        delay
+    }
+
+    /// Marks a save as scheduled, `focus_lost` if it is for a window losing focus.
+    ///
+    /// Returns `false` if a save is already scheduled, which then writes the changes too.
+    fn schedule(&self, focus_lost: bool) -> bool {
+        if focus_lost {
+            self.focus_lost_save_scheduled.store(true, Ordering::SeqCst);
+        }
+        !self.save_scheduled.swap(true, Ordering::SeqCst)
+    }
+
+    /// Marks the scheduled save as running, recording it as the last focus lost save
+    /// if it was scheduled for a window losing focus. Other saves don't throttle those.
+    fn start_scheduled_save(&self) {
+        self.save_scheduled.store(false, Ordering::SeqCst);
+        if self.focus_lost_save_scheduled.swap(false, Ordering::SeqCst) {
+            self.last_focus_lost_save
+                .lock()
+                .unwrap()
+                .replace(Instant::now());
+        }
+    }
+
+    /// Whether the window with the given label should be tracked and managed by this plugin.
+    fn is_tracked(&self, label: &str) -> bool {
+        if self.allowlist.is_empty() {
+            !self.denylist.contains(label)
+        } else {
+            self.allowlist.contains(label)
+        }
+    }
+}
+
 pub trait AppHandleExt {
-    /// Saves all open windows state to disk
+    /// Saves all open windows state to disk.
+    ///
+    /// `flags` apply to all windows, the flags set with [`Builder::with_window_flags`] are only
+    /// used by the saves the plugin triggers itself.
     fn save_window_state(&self, flags: StateFlags) -> Result<()>;
+    /// Saves all open windows state to the given path instead of the configured store,
+    /// e.g. to export a snapshot of the window layout. Uses the format set with [`Builder::with_format`].
+    ///
+    /// Returns the number of bytes written.
+    fn save_window_state_to(&self, path: &Path, flags: StateFlags) -> Result<u64>;
+    /// Removes the saved state of the window with the given label from the cache and disk,
+    /// so it is restored to its default state on the next launch.
+    fn remove_window_state(&self, label: &str) -> Result<()>;
+    /// Removes the saved state of all windows from the cache and disk.
+    fn clear_all_window_states(&self) -> Result<()>;
+    /// Whether the cached state of any window changed since it was last written to disk.
+    fn is_window_state_dirty(&self) -> bool;
+    /// Returns a copy of the cached state of the window with the given label, i.e. what is written on the next save.
+    ///
+    /// The cache is refreshed from the open windows when they are saved, closed or moved.
+    fn window_state(&self, label: &str) -> Option<WindowStateSnapshot>;
+}
+
+/// Refreshes the cached state of all open tracked windows, see [`PluginState::flags_for`] for `flags`.
+fn update_cached_states<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    state: &mut HashMap<String, WindowState>,
+    flags: Option<StateFlags>,
+) -> tauri::Result<()> {
+    let plugin_state = app.state::<PluginState>();
+    for (label, s) in state.iter_mut() {
+        if !plugin_state.is_tracked(label) {
+            continue;
+        }
+        if let Some(window) = app.get_window(label) {
+            window.update_state(s, plugin_state.flags_for(label, flags))?;
+        }
+    }
+    Ok(())
+}
+
+/// Refreshes the cached state of all open tracked windows and writes it to the store if it changed.
+///
+/// Unless `force` is set, writes are delayed until the interval set with [`Builder::with_min_save_interval`] has passed.
+fn save_cached_states<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    flags: Option<StateFlags>,
+    force: bool,
+) -> Result<()> {
+    let cache = app.state::<WindowStateCache>();
+    let mut state = cache.0.lock().unwrap();
+    update_cached_states(app, &mut state, flags)?;
+    emit_state(app, &state);
+    if let Some(delay) = app.state::<PluginState>().save_if_dirty(&state, force)? {
+        // the state stays dirty until the delayed save writes it
+        schedule_save(app, delay, flags, false);
+    }
+    Ok(())
+}
+
+/// Saves the state of all windows after `delay`, so changes that were throttled are still written.
+///
+/// Does nothing if a save is already scheduled, which then writes these changes too.
+/// `focus_lost` is set for the saves of windows losing focus, see [`PluginState::schedule`].
+fn schedule_save<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    delay: Duration,
+    flags: Option<StateFlags>,
+    focus_lost: bool,
+) {
+    if !app.state::<PluginState>().schedule(focus_lost) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(delay);
+        app.state::<PluginState>().start_scheduled_save();
+        let _ = save_cached_states(&app, flags, false);
+    });
+}
+
+/// Refreshes the cached state of all open tracked windows and writes it to `path`, see [`AppHandleExt::save_window_state_to`].
+fn save_cached_states_to<R: Runtime>(
+    app: &tauri::AppHandle<R>,
+    path: &Path,
+    flags: Option<StateFlags>,
+) -> Result<u64> {
+    let cache = app.state::<WindowStateCache>();
+    let mut state = cache.0.lock().unwrap();
+    update_cached_states(app, &mut state, flags)?;
+
+    let plugin_state = app.state::<PluginState>();
+    let bytes = encode(
+        plugin_state.format.serialize(&state)?,
+        plugin_state.compression,
+        plugin_state.encryption_key.as_ref(),
+    )?;
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    std::fs::write(path, &bytes)?;
+    Ok(bytes.len() as u64)
+}
+
+/// Emits the state of all windows to the frontend, if enabled with [`Builder::with_state_events`].
+fn emit_state<R: Runtime, M: Manager<R>>(manager: &M, state: &HashMap<String, WindowState>) {
+    if manager.state::<PluginState>().emit_state_changes {
+        let _ = manager.emit_all(STATE_CHANGE_EVENT, snapshots(state));
+    }
 }
 
 impl<R: Runtime> AppHandleExt for tauri::AppHandle<R> {
     fn save_window_state(&self, flags: StateFlags) -> Result<()> {
-        if let Some(app_dir) = self.path_resolver().app_config_dir() {
-            let state_path = app_dir.join(STATE_FILENAME);
-            let cache = self.state::<WindowStateCache>();
-            let mut state = cache.0.lock().unwrap();
-            for (label, s) in state.iter_mut() {
-                if let Some(window) = self.get_window(label) {
-                    window.update_state(s, flags)?;
-                }
-            }
+        save_cached_states(self, Some(flags), false)
+    }
+
+    fn save_window_state_to(&self, path: &Path, flags: StateFlags) -> Result<u64> {
+        save_cached_states_to(self, path, Some(flags))
+    }
+
+    fn remove_window_state(&self, label: &str) -> Result<()> {
+        let cache = self.state::<WindowStateCache>();
+        let mut state = cache.0.lock().unwrap();
+        state.remove(label);
+        emit_state(self, &state);
+        self.state::<PluginState>().save(&state)
+    }
+
+    fn clear_all_window_states(&self) -> Result<()> {
+        let cache = self.state::<WindowStateCache>();
+        let mut state = cache.0.lock().unwrap();
+        state.clear();
+        emit_state(self, &state);
+        self.state::<PluginState>().save(&state)
+    }
 
-            create_dir_all(&app_dir)
-                .map_err(Error::Io)
-                .and_then(|_| File::create(state_path).map_err(Into::into))
-                .and_then(|mut f| {
-                    f.write_all(&bincode::serialize(&*state).map_err(Error::Bincode)?)
-                        .map_err(Into::into)
-                })
-        } else {
-            Ok(())
-        }
+    fn is_window_state_dirty(&self) -> bool {
+        self.state::<PluginState>().dirty.load(Ordering::SeqCst)
+    }
+
+    fn window_state(&self, label: &str) -> Option<WindowStateSnapshot> {
+        let cache = self.state::<WindowStateCache>();
+        let state = cache.0.lock().unwrap();
+        state.get(label).map(Into::into)
     }
 }
 
 pub trait WindowExt {
-    /// Restores this window state from disk
+    /// Restores this window state from disk.
+    ///
+    /// `flags` are used as is, the flags set with [`Builder::with_window_flags`] are only
+    /// used by the restore the plugin triggers itself.
     fn restore_state(&self, flags: StateFlags) -> tauri::Result<()>;
 }
 
 impl<R: Runtime> WindowExt for Window<R> {
     fn restore_state(&self, flags: StateFlags) -> tauri::Result<()> {
+        let plugin_state = self.state::<PluginState>();
+        // windows excluded with the allowlist or denylist are neither restored nor cached
+        if !plugin_state.is_tracked(self.label()) {
+            return Ok(());
+        }
         let cache = self.state::<WindowStateCache>();
         let mut c = cache.0.lock().unwrap();
 
         let mut should_show = true;
+        let mut should_minimize = false;
 
         if let Some(state) = c.get(self.label()) {
             // avoid restoring the default zeroed state
-            if *state == WindowState::default() {
+            if state.is_default(flags) {
                 return Ok(());
             }
 
@@ -141,31 +1097,116 @@ impl<R: Runtime> WindowExt for Window<R> {
             }
 
             if flags.contains(StateFlags::SIZE) {
-                self.set_size(LogicalSize {
-                    width: state.width,
-                    height: state.height,
-                })?;
+                if state.width > 0. && state.height > 0. {
+                    self.restore_size(LogicalSize {
+                        width: state.width,
+                        height: state.height,
+                    })?;
+                } else if flags.contains(StateFlags::MAXIMIZED) && state.maximized {
+                    // the size isn't saved while the window is maximized, so a window that was
+                    // never unmaximized has no usable size, give it a sensible size to unmaximize to
+                    if let Some(monitor) = self.current_monitor()? {
+                        let geometry = RestoreGeometry::unmaximized_on((&monitor).into());
+                        self.set_size(geometry.size)?;
+                        self.set_position(geometry.position)?;
+                    }
+                }
             }
 
             if flags.contains(StateFlags::POSITION) {
-                let position = (state.x, state.y).into();
-                let size = (state.width, state.height).into();
-                // restore position to saved value if saved monitor exists
-                // otherwise, let the OS decide where to place the window
-                for m in self.available_monitors()? {
-                    if m.intersects(position, size) {
-                        self.set_position(PhysicalPosition {
-                            x: if state.maximized {
-                                state.prev_x
-                            } else {
-                                state.x
-                            },
-                            y: if state.maximized {
-                                state.prev_y
-                            } else {
-                                state.y
-                            },
-                        })?;
+                let saved_position = (state.x, state.y).into();
+                let saved_size = (state.width, state.height).into();
+                let (x, y) = if state.maximized {
+                    (state.prev_x, state.prev_y)
+                } else {
+                    (state.x, state.y)
+                };
+
+                let monitors = self.available_monitors()?;
+                let saved_monitor = state
+                    .monitor
+                    .as_ref()
+                    .filter(|_| flags.contains(StateFlags::MONITOR))
+                    .and_then(|saved| saved.find(&monitors).map(|m| (saved, m)));
+
+                let target = match saved_monitor {
+                    // restore relative to the monitor the window was on, wherever it is now
+                    Some((saved, m)) => Some((
+                        m,
+                        PhysicalPosition {
+                            x: m.position().x + x - saved.x,
+                            y: m.position().y + y - saved.y,
+                        },
+                    )),
+                    // restore position to saved value if saved monitor exists
+                    // otherwise, let the OS decide where to place the window
+                    None => monitors
+                        .iter()
+                        .find(|m| MonitorBounds::from(*m).intersects(saved_position, saved_size))
+                        .map(|m| (m, PhysicalPosition { x, y })),
+                };
+
+                if let Some((m, position)) = target {
+                    let mut position = MonitorBounds::from(m).rescale(position, state.scale_factor);
+
+                    // keep the title bar reachable if the window was above the monitor
+                    if plugin_state.clamp_position {
+                        position = MonitorBounds::from(m).clamp_title_bar(position);
+                    }
+
+                    let size = if state.width > 0. && state.height > 0. {
+                        LogicalSize {
+                            width: state.width,
+                            height: state.height,
+                        }
+                    } else {
+                        self.saved_size(m.scale_factor())?
+                    };
+                    let mut geometry = RestoreGeometry { position, size };
+
+                    if plugin_state.clamp_size {
+                        geometry.fit_size_to(m.into());
+                    }
+
+                    if let Some(on_restore_geometry) = &plugin_state.on_restore_geometry {
+                        on_restore_geometry(self.label(), m, &mut geometry);
+                        geometry.clamp_to(m.into());
+                    }
+
+                    if flags.contains(StateFlags::SIZE) && geometry.size != size {
+                        self.restore_size(geometry.size)?;
+                    }
+                    self.set_position(geometry.position)?;
+                } else if plugin_state.offscreen_fallback == OffscreenFallback::Center {
+                    // the monitors the window was on are gone
+                    let primary = self
+                        .primary_monitor()?
+                        .or_else(|| monitors.first().cloned());
+                    if let Some(m) = primary {
+                        let size = if state.width > 0. && state.height > 0. {
+                            LogicalSize {
+                                width: state.width,
+                                height: state.height,
+                            }
+                        } else {
+                            self.saved_size(m.scale_factor())?
+                        };
+                        let mut geometry = RestoreGeometry {
+                            position: *m.position(),
+                            size,
+                        };
+                        geometry.fit_size_to((&m).into());
+                        geometry.center_on((&m).into());
+
+                        if let Some(on_restore_geometry) = &plugin_state.on_restore_geometry {
+                            on_restore_geometry(self.label(), &m, &mut geometry);
+                            geometry.clamp_to((&m).into());
+                        }
+
+                        if flags.contains(StateFlags::SIZE) && geometry.size != size {
+                            self.restore_size(geometry.size)?;
+                        }
+                        self.set_position(geometry.position)?;
                     }
                 }
             }
@@ -179,6 +1220,7 @@ impl<R: Runtime> WindowExt for Window<R> {
             }
 
             should_show = state.visible;
+            should_minimize = flags.contains(StateFlags::MINIMIZED) && state.minimized;
         } else {
             let mut metadata = WindowState::default();
 
@@ -187,7 +1229,7 @@ impl<R: Runtime> WindowExt for Window<R> {
                     .current_monitor()?
                     .map(|m| m.scale_factor())
                     .unwrap_or(1.);
-                let size = self.inner_size()?.to_logical(scale_factor);
+                let size = self.saved_size(scale_factor)?;
                 metadata.width = size.width;
                 metadata.height = size.height;
             }
@@ -214,12 +1256,30 @@ impl<R: Runtime> WindowExt for Window<R> {
                 metadata.fullscreen = self.is_fullscreen()?;
             }
 
+            if flags.contains(StateFlags::MINIMIZED) {
+                metadata.minimized = self.is_minimized()?;
+            }
+
+            if flags.contains(StateFlags::MONITOR) {
+                metadata.monitor = self.current_monitor()?.as_ref().map(SavedMonitor::new);
+            }
+
             c.insert(self.label().into(), metadata);
+            plugin_state.mark_dirty();
+            emit_state(self, &c);
         }
 
         if flags.contains(StateFlags::VISIBLE) && should_show {
             self.show()?;
-            self.set_focus()?;
+            // focusing would bring a minimized window back up
+            if !should_minimize {
+                self.set_focus()?;
+            }
+        }
+
+        // minimize after showing, since showing the window may restore it on some platforms
+        if should_minimize {
+            self.minimize()?;
         }
 
         Ok(())
@@ -227,11 +1287,63 @@ impl<R: Runtime> WindowExt for Window<R> {
 }
 
 trait WindowExtInternal {
+    /// Updates `state` with the current window state, marking the cache dirty if it changed.
     fn update_state(&self, state: &mut WindowState, flags: StateFlags) -> tauri::Result<()>;
+    fn capture_state(&self, state: &mut WindowState, flags: StateFlags) -> tauri::Result<()>;
+    /// The size to save, depending on the configured [`SizeKind`].
+    fn saved_size(&self, scale_factor: f64) -> tauri::Result<LogicalSize<f64>>;
+    /// Restores a size saved with [`WindowExtInternal::saved_size`].
+    fn restore_size(&self, size: LogicalSize<f64>) -> tauri::Result<()>;
 }
 
 impl<R: Runtime> WindowExtInternal for Window<R> {
+    fn saved_size(&self, scale_factor: f64) -> tauri::Result<LogicalSize<f64>> {
+        let size = match self.state::<PluginState>().size_kind {
+            SizeKind::Inner => self.inner_size()?,
+            SizeKind::Outer => self.outer_size()?,
+        };
+        Ok(size.to_logical(scale_factor))
+    }
+
+    fn restore_size(&self, size: LogicalSize<f64>) -> tauri::Result<()> {
+        let size = match self.state::<PluginState>().size_kind {
+            SizeKind::Inner => size,
+            SizeKind::Outer => {
+                // `set_size` sets the inner size, so leave room for the decorations
+                let scale_factor = self.scale_factor()?;
+                let inner = self.inner_size()?.to_logical::<f64>(scale_factor);
+                let outer = self.outer_size()?.to_logical::<f64>(scale_factor);
+                LogicalSize {
+                    width: (size.width - (outer.width - inner.width)).max(1.),
+                    height: (size.height - (outer.height - inner.height)).max(1.),
+                }
+            }
+        };
+        self.set_size(size)
+    }
+
     fn update_state(&self, state: &mut WindowState, flags: StateFlags) -> tauri::Result<()> {
+        let plugin_state = self.state::<PluginState>();
+        let previous = state.clone();
+        let result = self.capture_state(state, flags);
+        if *state != previous {
+            plugin_state.mark_dirty();
+        }
+        result
+    }
+
+    fn capture_state(&self, state: &mut WindowState, flags: StateFlags) -> tauri::Result<()> {
+        let plugin_state = self.state::<PluginState>();
+
+        // keep the saved state of windows that were never shown in this run,
+        // only recording that they are hidden
+        if !plugin_state.should_capture(self) {
+            if flags.contains(StateFlags::VISIBLE) {
+                state.visible = false;
+            }
+            return Ok(());
+        }
+
         let is_maximized = match flags.intersects(StateFlags::MAXIMIZED | StateFlags::SIZE) {
             true => self.is_maximized()?,
             false => false,
@@ -245,6 +1357,14 @@ impl<R: Runtime> WindowExtInternal for Window<R> {
             state.fullscreen = self.is_fullscreen()?;
         }
 
+        if flags.contains(StateFlags::MINIMIZED) {
+            state.minimized = self.is_minimized()?;
+        }
+
+        if flags.contains(StateFlags::MONITOR) {
+            state.monitor = self.current_monitor()?.as_ref().map(SavedMonitor::new);
+        }
+
         if flags.contains(StateFlags::DECORATIONS) {
             state.decorated = self.is_decorated()?;
         }
@@ -258,7 +1378,7 @@ impl<R: Runtime> WindowExtInternal for Window<R> {
                 .current_monitor()?
                 .map(|m| m.scale_factor())
                 .unwrap_or(1.);
-            let size = self.inner_size()?.to_logical(scale_factor);
+            let size = self.saved_size(scale_factor)?;
 
             // It doesn't make sense to save a window with 0 height or width
             if size.width > 0. && size.height > 0. && !is_maximized {
@@ -271,20 +1391,67 @@ impl<R: Runtime> WindowExtInternal for Window<R> {
             let position = self.outer_position()?;
             state.x = position.x;
             state.y = position.y;
+            if let Some(monitor) = self.current_monitor()? {
+                state.scale_factor = monitor.scale_factor();
+            }
         }
 
         Ok(())
     }
 }
 
-#[derive(Default)]
-pub struct Builder {
+/// Evaluated for every window to decide whether its initial state restore should be skipped.
+type SkipInitialStatePredicate<R> = Box<dyn Fn(&Window<R>) -> bool + Send + Sync>;
+
+pub struct Builder<R: Runtime = tauri::Wry> {
+    allowlist: HashSet<String>,
     denylist: HashSet<String>,
     skip_initial_state: HashSet<String>,
+    skip_initial_state_if: Option<SkipInitialStatePredicate<R>>,
     state_flags: StateFlags,
+    window_flags: HashMap<String, StateFlags>,
+    clamp_position: bool,
+    clamp_size: bool,
+    store: Option<Arc<dyn StateStore + Send + Sync>>,
+    format: StateFormat,
+    compression: bool,
+    encryption_key: Option<[u8; 32]>,
+    size_kind: SizeKind,
+    on_restore_geometry: Option<OnRestoreGeometry>,
+    emit_state_changes: bool,
+    skip_never_shown: bool,
+    save_on_focus_lost: bool,
+    min_save_interval: Option<Duration>,
+    offscreen_fallback: OffscreenFallback,
+}
+
+impl<R: Runtime> Default for Builder<R> {
+    fn default() -> Self {
+        Self {
+            allowlist: Default::default(),
+            denylist: Default::default(),
+            skip_initial_state: Default::default(),
+            skip_initial_state_if: None,
+            state_flags: Default::default(),
+            window_flags: Default::default(),
+            clamp_position: Default::default(),
+            clamp_size: Default::default(),
+            store: None,
+            format: Default::default(),
+            compression: false,
+            encryption_key: None,
+            size_kind: Default::default(),
+            on_restore_geometry: None,
+            emit_state_changes: false,
+            skip_never_shown: false,
+            save_on_focus_lost: false,
+            min_save_interval: None,
+            offscreen_fallback: Default::default(),
+        }
+    }
 }
 
-impl Builder {
+impl<R: Runtime> Builder<R> {
     pub fn new() -> Self {
         Self::default()
     }
@@ -295,61 +1462,277 @@ impl Builder {
         self
     }
 
+    /// Sets the state flags for the window with the given label,
+    /// overriding the flags set with [`Self::with_state_flags`] for that window.
+    ///
+    /// The override applies to the saves and restores the plugin triggers itself, and to the
+    /// commands called without flags. Flags passed explicitly are used as is.
+    pub fn with_window_flags(mut self, label: &str, flags: StateFlags) -> Self {
+        self.window_flags.insert(label.into(), flags);
+        self
+    }
+
     /// Sets a list of windows that shouldn't be tracked and managed by this plugin
     /// for example splash screen windows.
+    ///
+    /// This is mutually exclusive with [`Self::with_allowlist`], if both are set
+    /// the denylist is ignored.
     pub fn with_denylist(mut self, denylist: &[&str]) -> Self {
         self.denylist = denylist.iter().map(|l| l.to_string()).collect();
         self
     }
 
+    /// Sets a list of windows that should exclusively be tracked and managed by this plugin,
+    /// all other windows are ignored. An empty list means all windows are tracked.
+    ///
+    /// This is mutually exclusive with [`Self::with_denylist`], if both are set
+    /// the denylist is ignored.
+    pub fn with_allowlist(mut self, allowlist: &[&str]) -> Self {
+        self.allowlist = allowlist.iter().map(|l| l.to_string()).collect();
+        self
+    }
+
+    /// Whether restored positions should be clamped so the top edge of the window,
+    /// where the title bar usually is, stays on the monitor it is restored to.
+    ///
+    /// Defaults to `false`.
+    pub fn with_position_clamping(mut self, clamp: bool) -> Self {
+        self.clamp_position = clamp;
+        self
+    }
+
+    /// Whether restored sizes should be shrunk to fit on the monitor the window is restored to,
+    /// preserving their aspect ratio. This requires [`StateFlags::POSITION`] to be tracked
+    /// to find the monitor.
+    ///
+    /// Defaults to `false`.
+    pub fn with_max_size_clamp(mut self, clamp: bool) -> Self {
+        self.clamp_size = clamp;
+        self
+    }
+
+    /// Sets whether the inner or the outer size of windows is saved and restored.
+    ///
+    /// Defaults to [`SizeKind::Inner`].
+    pub fn with_size_kind(mut self, kind: SizeKind) -> Self {
+        self.size_kind = kind;
+        self
+    }
+
+    /// Sets how windows are restored when their saved position isn't on any of the available monitors,
+    /// e.g. because the external monitor they were on was disconnected.
+    ///
+    /// Defaults to [`OffscreenFallback::LeaveToOs`].
+    pub fn with_offscreen_fallback(mut self, fallback: OffscreenFallback) -> Self {
+        self.offscreen_fallback = fallback;
+        self
+    }
+
+    /// Sets a hook to adjust the geometry of a window right before it is restored,
+    /// e.g. to implement custom placement or DPI policies.
+    ///
+    /// The hook is called with the window label and the monitor the window is restored to,
+    /// when [`StateFlags::POSITION`] is tracked and a target monitor was found.
+    /// The size is only applied when [`StateFlags::SIZE`] is tracked.
+    /// A geometry that doesn't fit on the monitor is shrunk and moved onto it.
+    pub fn on_restore_geometry<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &Monitor, &mut RestoreGeometry) + Send + Sync + 'static,
+    {
+        self.on_restore_geometry.replace(Arc::new(hook));
+        self
+    }
+
+    /// Whether the state of all windows should be emitted to the frontend as the
+    /// [`STATE_CHANGE_EVENT`] event whenever it changes or is saved, e.g. to mirror it into a frontend store.
+    ///
+    /// To take over persistence entirely, combine this with a custom store set with [`Self::with_store`].
+    ///
+    /// Defaults to `false`.
+    pub fn with_state_events(mut self, emit: bool) -> Self {
+        self.emit_state_changes = emit;
+        self
+    }
+
+    /// Sets a custom storage backend for the window states.
+    ///
+    /// Defaults to a [`FileStore`] saving to [`StateFormat::filename`] in the app config directory.
+    pub fn with_store(mut self, store: impl StateStore + Send + Sync + 'static) -> Self {
+        self.store.replace(Arc::new(store));
+        self
+    }
+
+    /// Sets the format of the state file, the file extension is chosen accordingly.
+    ///
+    /// This has no effect when a custom store is set with [`Self::with_store`].
+    pub fn with_format(mut self, format: StateFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Whether to gzip compress the state file. Defaults to `false`.
+    ///
+    /// Both compressed and uncompressed files are loaded regardless of this setting,
+    /// so it can be toggled without losing the saved state.
+    /// This has no effect when a custom store is set with [`Self::with_store`].
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Encrypts the state file at rest with ChaCha20-Poly1305 using the given key.
+    ///
+    /// Unencrypted files are still loaded and encrypted on the next save. If the file can't be
+    /// decrypted, e.g. because the key changed, the windows start from their default state.
+    /// Losing the key means losing the saved layout.
+    /// This has no effect when a custom store is set with [`Self::with_store`].
+    pub fn with_encryption(mut self, key: [u8; 32]) -> Self {
+        self.encryption_key.replace(key);
+        self
+    }
+
     /// Adds the given window label to a list of windows to skip initial state restore.
     pub fn skip_initial_state(mut self, label: &str) -> Self {
         self.skip_initial_state.insert(label.into());
         self
     }
 
-    pub fn build<R: Runtime>(self) -> TauriPlugin<R> {
-        let flags = self.state_flags;
+    /// Sets a predicate to decide at runtime whether a window should skip its initial state restore,
+    /// evaluated in addition to the labels added with [`Self::skip_initial_state`].
+    pub fn skip_initial_state_if<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(&Window<R>) -> bool + Send + Sync + 'static,
+    {
+        self.skip_initial_state_if.replace(Box::new(predicate));
+        self
+    }
+
+    /// Whether to skip capturing the state of windows that were never visible since the app started,
+    /// keeping their previously saved state instead of saving the geometry of a window the user never saw.
+    ///
+    /// Windows that start hidden and are shown later are captured as usual once shown.
+    ///
+    /// Defaults to `false`.
+    pub fn skip_never_shown(mut self, skip: bool) -> Self {
+        self.skip_never_shown = skip;
+        self
+    }
+
+    /// Whether to save the state of all windows when a tracked window loses focus,
+    /// in addition to when windows are closed and the app exits.
+    ///
+    /// Saves are throttled so rapidly switching focus doesn't write the state file every time,
+    /// a save within the interval of the last one is delayed until the interval has passed.
+    ///
+    /// Defaults to `false`.
+    pub fn save_on_focus_lost(mut self, save: bool) -> Self {
+        self.save_on_focus_lost = save;
+        self
+    }
+
+    /// Sets the minimum interval between writes of the state file.
+    ///
+    /// Saves within the interval of the last write are delayed until it has passed, so frequent saves
+    /// are coalesced into a single write. Saves are always skipped if no window state changed since
+    /// the last write.
+    ///
+    /// By default every save that has changes writes the state file.
+    pub fn with_min_save_interval(mut self, interval: Duration) -> Self {
+        self.min_save_interval.replace(interval);
+        self
+    }
+
+    pub fn build(self) -> TauriPlugin<R> {
+        if !self.allowlist.is_empty() && !self.denylist.is_empty() {
+            log::error!(
+                "window-state: `with_allowlist` and `with_denylist` are mutually exclusive, ignoring the denylist"
+            );
+        }
+        let plugin_state = PluginState {
+            allowlist: self.allowlist,
+            denylist: self.denylist,
+            clamp_position: self.clamp_position,
+            clamp_size: self.clamp_size,
+            size_kind: self.size_kind,
+            state_flags: self.state_flags,
+            window_flags: self.window_flags,
+            store: self.store,
+            on_restore_geometry: self.on_restore_geometry,
+            format: self.format,
+            compression: self.compression,
+            encryption_key: self.encryption_key,
+            emit_state_changes: self.emit_state_changes,
+            skip_never_shown: self.skip_never_shown,
+            offscreen_fallback: self.offscreen_fallback,
+            shown: Default::default(),
+            save_on_focus_lost: self.save_on_focus_lost,
+            last_focus_lost_save: Default::default(),
+            save_scheduled: Default::default(),
+            focus_lost_save_scheduled: Default::default(),
+            dirty: Default::default(),
+            min_save_interval: self.min_save_interval,
+            last_save: Default::default(),
+        };
+        let setup_plugin_state = plugin_state.clone();
+        let format = self.format;
+        let compression = self.compression;
+        let encryption_key = self.encryption_key;
+
         PluginBuilder::new("window-state")
             .invoke_handler(tauri::generate_handler![
                 cmd::save_window_state,
-                cmd::restore_state
+                cmd::save_window_state_to,
+                cmd::restore_state,
+                cmd::remove_window_state,
+                cmd::clear_all_window_states
             ])
-            .setup(|app| {
-                let cache: Arc<Mutex<HashMap<String, WindowState>>> = if let Some(app_dir) =
-                    app.path_resolver().app_config_dir()
-                {
-                    let state_path = app_dir.join(STATE_FILENAME);
-                    if state_path.exists() {
-                        Arc::new(Mutex::new(
-                            tauri::api::file::read_binary(state_path)
-                                .map_err(Error::TauriApi)
-                                .and_then(|state| bincode::deserialize(&state).map_err(Into::into))
-                                .unwrap_or_default(),
-                        ))
-                    } else {
-                        Default::default()
+            .setup(move |app| {
+                let mut plugin_state = setup_plugin_state;
+                if plugin_state.store.is_none() {
+                    if let Some(app_dir) = app.path_resolver().app_config_dir() {
+                        let mut store = FileStore::new(app_dir.join(format.filename()))
+                            .with_format(format)
+                            .with_compression(compression);
+                        if let Some(key) = encryption_key {
+                            store = store.with_encryption(key);
+                        }
+                        plugin_state.store.replace(Arc::new(store));
                     }
-                } else {
-                    Default::default()
-                };
-                app.manage(WindowStateCache(cache));
+                }
+
+                let cache: HashMap<String, WindowState> = plugin_state
+                    .store
+                    .as_ref()
+                    .and_then(|store| store.load().ok())
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(label, state)| (label, state.into()))
+                    .collect();
+                app.manage(WindowStateCache(Arc::new(Mutex::new(cache))));
+                app.manage(plugin_state);
                 Ok(())
             })
             .on_webview_ready(move |window| {
-                if self.denylist.contains(window.label()) {
+                if !plugin_state.is_tracked(window.label()) {
                     return;
                 }
 
-                if !self.skip_initial_state.contains(window.label()) {
-                    let _ = window.restore_state(self.state_flags);
+                let skip_initial_state = self.skip_initial_state.contains(window.label())
+                    || self
+                        .skip_initial_state_if
+                        .as_ref()
+                        .map_or(false, |skip| skip(&window));
+                if !skip_initial_state {
+                    let _ = window.restore_state(plugin_state.flags_for(window.label(), None));
                 }
 
                 let cache = window.state::<WindowStateCache>();
                 let cache = cache.0.clone();
                 let label = window.label().to_string();
                 let window_clone = window.clone();
-                let flags = self.state_flags;
+                let flags = plugin_state.flags_for(&label, None);
+                let window_plugin_state = plugin_state.clone();
+                window_plugin_state.mark_if_shown(&window);
 
                 // insert a default state if this window should be tracked and
                 // the disk cache doesn't have a state for it
@@ -366,10 +1749,14 @@ impl Builder {
                         let mut c = cache.lock().unwrap();
                         if let Some(state) = c.get_mut(&label) {
                             let _ = window_clone.update_state(state, flags);
+                            emit_state(&window_clone, &c);
                         }
                     }
 
-                    WindowEvent::Moved(position) if flags.contains(StateFlags::POSITION) => {
+                    WindowEvent::Moved(position)
+                        if flags.contains(StateFlags::POSITION)
+                            && window_plugin_state.should_capture(&window_clone) =>
+                    {
                         let mut c = cache.lock().unwrap();
                         if let Some(state) = c.get_mut(&label) {
                             state.prev_x = state.x;
@@ -377,46 +1764,637 @@ impl Builder {
 
                             state.x = position.x;
                             state.y = position.y;
+                            if let Ok(Some(monitor)) = window_clone.current_monitor() {
+                                state.scale_factor = monitor.scale_factor();
+                            }
+                            window_plugin_state.mark_dirty();
+                            emit_state(&window_clone, &c);
+                        }
+                    }
+
+                    WindowEvent::Focused(false) if window_plugin_state.save_on_focus_lost => {
+                        let app = window_clone.app_handle();
+                        let delay = window_plugin_state.focus_lost_save_delay();
+                        if delay.is_zero() {
+                            let _ = save_cached_states(&app, None, false);
+                        } else {
+                            // save once the interval has passed instead of dropping the changes
+                            schedule_save(&app, delay, None, true);
                         }
                     }
+
+                    WindowEvent::Focused(true) | WindowEvent::Resized(_) => {
+                        window_plugin_state.mark_if_shown(&window_clone);
+                    }
                     _ => {}
                 });
             })
             .on_event(move |app, event| {
                 if let RunEvent::Exit = event {
-                    let _ = app.save_window_state(flags);
+                    // always write pending changes on exit, regardless of the minimum save interval
+                    let _ = save_cached_states(app, None, true);
                 }
             })
             .build()
     }
 }
 
-trait MonitorExt {
-    fn intersects(&self, position: PhysicalPosition<i32>, size: LogicalSize<u32>) -> bool;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-impl MonitorExt for Monitor {
-    fn intersects(&self, position: PhysicalPosition<i32>, size: LogicalSize<u32>) -> bool {
-        let size = size.to_physical::<u32>(self.scale_factor());
+    fn monitor(x: i32, y: i32, width: u32, height: u32, scale_factor: f64) -> MonitorBounds {
+        MonitorBounds {
+            position: PhysicalPosition { x, y },
+            size: PhysicalSize { width, height },
+            scale_factor,
+        }
+    }
 
-        let PhysicalPosition { x, y } = *self.position();
-        let PhysicalSize { width, height } = *self.size();
+    #[test]
+    fn intersects_when_any_corner_is_on_the_monitor() {
+        let m = monitor(0, 0, 1920, 1080, 1.);
+        let size = LogicalSize {
+            width: 800,
+            height: 600,
+        };
+        assert!(m.intersects(PhysicalPosition { x: 100, y: 100 }, size));
+        // only the bottom right corner is on the monitor
+        assert!(m.intersects(PhysicalPosition { x: -700, y: -500 }, size));
+        assert!(!m.intersects(PhysicalPosition { x: 1920, y: 0 }, size));
+        assert!(!m.intersects(PhysicalPosition { x: -900, y: 0 }, size));
+    }
 
-        let left = x;
-        let right = x + width as i32;
-        let top = y;
-        let bottom = y + height as i32;
+    #[test]
+    fn intersects_scales_the_window_size() {
+        let m = monitor(1000, 0, 1000, 1000, 2.);
+        let size = LogicalSize {
+            width: 300,
+            height: 300,
+        };
+        // 600 physical pixels wide at a scale factor of 2, reaching onto the monitor
+        assert!(m.intersects(PhysicalPosition { x: 500, y: 100 }, size));
+        assert!(!m.intersects(PhysicalPosition { x: 300, y: 100 }, size));
+    }
 
-        [
-            (position.x, position.y),
-            (position.x + size.width as i32, position.y),
-            (position.x, position.y + size.height as i32),
+    #[test]
+    fn clamp_title_bar_moves_windows_above_the_monitor_down() {
+        let m = monitor(0, 100, 1920, 1080, 1.);
+        assert_eq!(
+            m.clamp_title_bar(PhysicalPosition { x: -50, y: 20 }),
+            PhysicalPosition { x: -50, y: 100 }
+        );
+        assert_eq!(
+            m.clamp_title_bar(PhysicalPosition { x: 50, y: 500 }),
+            PhysicalPosition { x: 50, y: 500 }
+        );
+
+        let m = monitor(0, 0, 1920, 1080, 1.);
+        assert_eq!(
+            m.clamp_title_bar(PhysicalPosition { x: 100, y: -50 }),
+            PhysicalPosition { x: 100, y: 0 }
+        );
+    }
+
+    #[test]
+    fn clamp_title_bar_keeps_windows_on_monitors_above_the_primary() {
+        // a secondary monitor stacked above the primary one has negative coordinates
+        let m = monitor(0, -1080, 1920, 1080, 1.);
+        assert_eq!(
+            m.clamp_title_bar(PhysicalPosition { x: 100, y: -1000 }),
+            PhysicalPosition { x: 100, y: -1000 }
+        );
+        assert_eq!(
+            m.clamp_title_bar(PhysicalPosition { x: 100, y: -1100 }),
+            PhysicalPosition { x: 100, y: -1080 }
+        );
+    }
+
+    #[test]
+    fn clamp_to_moves_the_geometry_onto_the_monitor() {
+        let m = monitor(1920, 0, 1920, 1080, 1.);
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 3500, y: -100 },
+            size: LogicalSize {
+                width: 800.,
+                height: 600.,
+            },
+        };
+        geometry.clamp_to(m);
+        assert_eq!(geometry.position, PhysicalPosition { x: 3040, y: 0 });
+        assert_eq!(
+            geometry.size,
+            LogicalSize {
+                width: 800.,
+                height: 600.
+            }
+        );
+    }
+
+    #[test]
+    fn center_on_centers_the_physical_size() {
+        let m = monitor(0, 0, 2000, 1000, 2.);
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 0, y: 0 },
+            size: LogicalSize {
+                width: 500.,
+                height: 250.,
+            },
+        };
+        geometry.center_on(m);
+        assert_eq!(geometry.position, PhysicalPosition { x: 500, y: 250 });
+    }
+
+    fn sample_states() -> HashMap<String, WindowState> {
+        let mut states = HashMap::new();
+        states.insert(
+            "main".to_string(),
+            WindowState {
+                width: 800.,
+                height: 600.,
+                x: 100,
+                y: -20,
+                maximized: true,
+                monitor: Some(SavedMonitor {
+                    name: Some("DP-1".into()),
+                    x: 0,
+                    y: 0,
+                    width: 1920,
+                    height: 1080,
+                }),
+                scale_factor: 1.5,
+                ..Default::default()
+            },
+        );
+        states.insert("settings".to_string(), WindowState::default());
+        states
+    }
+
+    fn sample_snapshots() -> HashMap<String, WindowStateSnapshot> {
+        snapshots(&sample_states())
+    }
+
+    #[test]
+    fn file_store_round_trips_every_format() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in [
+            StateFormat::Bincode,
+            StateFormat::Json,
+            StateFormat::JsonPretty,
+            StateFormat::Toml,
+        ] {
+            let store = FileStore::new(dir.path().join(format.filename())).with_format(format);
+            store.save(&sample_snapshots()).unwrap();
+            assert_eq!(store.load().unwrap(), sample_snapshots(), "{format:?}");
+        }
+    }
+
+    #[test]
+    fn file_store_loads_an_empty_state_if_the_file_is_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = FileStore::new(dir.path().join(STATE_FILENAME));
+        assert!(store.load().unwrap().is_empty());
+    }
+
+    #[test]
+    fn file_store_replaces_the_file_without_leaving_temporary_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join(STATE_FILENAME);
+        let store = FileStore::new(&path);
+
+        store.save(&sample_snapshots()).unwrap();
+        let mut states = sample_snapshots();
+        states.remove("settings");
+        store.save(&states).unwrap();
+
+        assert_eq!(store.load().unwrap(), states);
+        let files = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        assert_eq!(files, [STATE_FILENAME]);
+    }
+
+    #[test]
+    fn fit_size_to_shrinks_oversized_windows_preserving_the_aspect_ratio() {
+        let m = monitor(0, 0, 1920, 1080, 1.);
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 0, y: 0 },
+            size: LogicalSize {
+                width: 3840.,
+                height: 1080.,
+            },
+        };
+        geometry.fit_size_to(m);
+        assert_eq!(
+            geometry.size,
+            LogicalSize {
+                width: 1920.,
+                height: 540.
+            }
+        );
+    }
+
+    #[test]
+    fn fit_size_to_uses_the_logical_monitor_size() {
+        // 1280x720 logical pixels at a scale factor of 2
+        let m = monitor(0, 0, 2560, 1440, 2.);
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 0, y: 0 },
+            size: LogicalSize {
+                width: 1600.,
+                height: 720.,
+            },
+        };
+        geometry.fit_size_to(m);
+        assert_eq!(
+            geometry.size,
+            LogicalSize {
+                width: 1280.,
+                height: 576.
+            }
+        );
+    }
+
+    #[test]
+    fn fit_size_to_keeps_windows_that_fit() {
+        let m = monitor(0, 0, 1920, 1080, 1.);
+        let size = LogicalSize {
+            width: 800.,
+            height: 600.,
+        };
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 0, y: 0 },
+            size,
+        };
+        geometry.fit_size_to(m);
+        assert_eq!(geometry.size, size);
+    }
+
+    #[test]
+    fn clamp_to_shrinks_the_size_to_the_monitor() {
+        let m = monitor(0, 0, 1920, 1080, 1.);
+        let mut geometry = RestoreGeometry {
+            position: PhysicalPosition { x: 100, y: 100 },
+            size: LogicalSize {
+                width: 2500.,
+                height: 600.,
+            },
+        };
+        geometry.clamp_to(m);
+        assert_eq!(
+            geometry.size,
+            LogicalSize {
+                width: 1920.,
+                height: 600.
+            }
+        );
+        assert_eq!(geometry.position, PhysicalPosition { x: 0, y: 100 });
+    }
+
+    #[test]
+    fn bincode_files_are_saved_with_the_magic_and_version() {
+        let bytes = StateFormat::Bincode.serialize(&sample_states()).unwrap();
+        let versioned = bytes.strip_prefix(BINCODE_MAGIC).unwrap();
+        assert_eq!(
+            bincode::deserialize::<u32>(versioned).unwrap(),
+            STATE_VERSION
+        );
+    }
+
+    #[test]
+    fn unversioned_bincode_files_are_upgraded() {
+        // the layout of the window states before the schema was versioned
+        let mut unversioned = HashMap::new();
+        unversioned.insert(
+            "main".to_string(),
             (
-                position.x + size.width as i32,
-                position.y + size.height as i32,
+                800f64, 600f64, 100i32, -20i32, 10i32, 20i32, true, false, true, false,
+            ),
+        );
+        let bytes = bincode::serialize(&unversioned).unwrap();
+
+        let states = StateFormat::Bincode.deserialize(&bytes).unwrap();
+        assert_eq!(
+            states["main"],
+            WindowState {
+                width: 800.,
+                height: 600.,
+                x: 100,
+                y: -20,
+                prev_x: 10,
+                prev_y: 20,
+                maximized: true,
+                visible: false,
+                decorated: true,
+                fullscreen: false,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn json_files_missing_fields_load_with_their_defaults() {
+        let unversioned = br#"{"main":{"width":800.0,"height":600.0,"x":1,"y":2}}"#;
+        let versioned =
+            br#"{"version":1,"windows":{"main":{"width":800.0,"height":600.0,"x":1,"y":2}}}"#;
+        let expected = WindowState {
+            width: 800.,
+            height: 600.,
+            x: 1,
+            y: 2,
+            ..Default::default()
+        };
+        for bytes in [&unversioned[..], &versioned[..]] {
+            let states = StateFormat::Json.deserialize(bytes).unwrap();
+            assert_eq!(states["main"], expected);
+        }
+    }
+
+    #[test]
+    fn version_2_bincode_files_load_without_the_scale_factor() {
+        let monitor = SavedMonitor {
+            name: Some("DP-1".into()),
+            x: 0,
+            y: 0,
+            width: 1920,
+            height: 1080,
+        };
+        // the layout of the window states before the scale factor was added
+        let mut windows = HashMap::new();
+        windows.insert(
+            "main".to_string(),
+            (
+                800f64,
+                600f64,
+                100i32,
+                -20i32,
+                10i32,
+                20i32,
+                false,
+                true,
+                true,
+                false,
+                true,
+                Some(monitor.clone()),
             ),
+        );
+        let bytes = [
+            BINCODE_MAGIC,
+            &bincode::serialize(&(2u32, windows)).unwrap(),
         ]
-        .into_iter()
-        .any(|(x, y)| x >= left && x < right && y >= top && y < bottom)
+        .concat();
+
+        let states = StateFormat::Bincode.deserialize(&bytes).unwrap();
+        let expected = WindowState {
+            width: 800.,
+            height: 600.,
+            x: 100,
+            y: -20,
+            prev_x: 10,
+            prev_y: 20,
+            minimized: true,
+            monitor: Some(monitor),
+            ..Default::default()
+        };
+        assert_eq!(states["main"], expected);
+
+        // and are saved with the current version from then on
+        let bytes = StateFormat::Bincode.serialize(&states).unwrap();
+        assert_eq!(
+            StateFormat::Bincode.deserialize(&bytes).unwrap()["main"],
+            expected
+        );
+    }
+
+    #[test]
+    fn compressed_state_round_trips() {
+        let bytes = StateFormat::Json.serialize(&sample_states()).unwrap();
+        let compressed = compress(&bytes).unwrap();
+        assert!(compressed.starts_with(GZIP_MAGIC));
+        assert_eq!(decompress(compressed).unwrap(), bytes);
+    }
+
+    #[test]
+    fn uncompressed_state_is_decompressed_as_is() {
+        let bytes = StateFormat::Bincode.serialize(&sample_states()).unwrap();
+        assert_eq!(decompress(bytes.clone()).unwrap(), bytes);
+    }
+
+    #[test]
+    fn file_store_loads_files_saved_with_or_without_compression() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILENAME);
+
+        FileStore::new(&path)
+            .with_compression(true)
+            .save(&sample_snapshots())
+            .unwrap();
+        assert!(std::fs::read(&path).unwrap().starts_with(GZIP_MAGIC));
+        assert_eq!(FileStore::new(&path).load().unwrap(), sample_snapshots());
+
+        FileStore::new(&path).save(&sample_snapshots()).unwrap();
+        assert_eq!(
+            FileStore::new(&path).with_compression(true).load().unwrap(),
+            sample_snapshots()
+        );
+    }
+
+    #[test]
+    fn encrypted_state_round_trips() {
+        let key = [7; 32];
+        let bytes = StateFormat::Bincode.serialize(&sample_states()).unwrap();
+        let encrypted = encrypt(&bytes, &key).unwrap();
+        assert!(encrypted.starts_with(ENCRYPTION_MAGIC));
+        assert_ne!(
+            encrypted[ENCRYPTION_MAGIC.len() + 1 + NONCE_LEN..],
+            bytes[..]
+        );
+        assert_eq!(decrypt(encrypted, Some(&key)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn encrypted_state_fails_to_decrypt_without_the_right_key() {
+        let encrypted = encrypt(b"state", &[7; 32]).unwrap();
+        assert!(matches!(
+            decrypt(encrypted.clone(), Some(&[8; 32])),
+            Err(Error::Encryption)
+        ));
+        assert!(matches!(
+            decrypt(encrypted.clone(), None),
+            Err(Error::Encryption)
+        ));
+
+        let truncated = encrypted[..ENCRYPTION_MAGIC.len() + 1].to_vec();
+        assert!(matches!(
+            decrypt(truncated, Some(&[7; 32])),
+            Err(Error::Encryption)
+        ));
+    }
+
+    #[test]
+    fn file_store_encrypts_plaintext_files_on_the_next_save() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILENAME);
+        FileStore::new(&path).save(&sample_snapshots()).unwrap();
+
+        let store = FileStore::new(&path)
+            .with_compression(true)
+            .with_encryption([7; 32]);
+        assert_eq!(store.load().unwrap(), sample_snapshots());
+        store.save(&sample_snapshots()).unwrap();
+
+        assert!(std::fs::read(&path).unwrap().starts_with(ENCRYPTION_MAGIC));
+        assert_eq!(store.load().unwrap(), sample_snapshots());
+        assert!(matches!(
+            FileStore::new(&path).with_encryption([8; 32]).load(),
+            Err(Error::Encryption)
+        ));
+    }
+
+    #[test]
+    fn focus_lost_saves_within_the_interval_are_delayed() {
+        let state = PluginState::default();
+        assert!(state.focus_lost_save_delay().is_zero());
+
+        let delay = state.focus_lost_save_delay();
+        assert!(!delay.is_zero());
+        assert!(delay <= FOCUS_LOST_SAVE_INTERVAL);
+        let first = *state.last_focus_lost_save.lock().unwrap();
+
+        // a save delayed by the min save interval doesn't count as a focus lost save
+        assert!(state.schedule(false));
+        state.start_scheduled_save();
+        assert_eq!(*state.last_focus_lost_save.lock().unwrap(), first);
+        assert!(!state.focus_lost_save_delay().is_zero());
+
+        // the delayed focus lost save does, even if it joined an already scheduled save
+        state.last_focus_lost_save.lock().unwrap().take();
+        assert!(state.schedule(false));
+        assert!(!state.schedule(true));
+        state.start_scheduled_save();
+        assert!(state.last_focus_lost_save.lock().unwrap().is_some());
+        assert!(!state.save_scheduled.load(Ordering::SeqCst));
+        assert!(!state.focus_lost_save_scheduled.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn saves_within_the_min_save_interval_are_delayed() {
+        let state = PluginState {
+            min_save_interval: Some(Duration::from_secs(60)),
+            ..Default::default()
+        };
+        let states = sample_states();
+        assert_eq!(state.save_if_dirty(&states, false).unwrap(), None);
+
+        state.mark_dirty();
+        assert_eq!(state.save_if_dirty(&states, false).unwrap(), None);
+        assert!(!state.dirty.load(Ordering::SeqCst));
+
+        state.mark_dirty();
+        let delay = state.save_if_dirty(&states, false).unwrap().unwrap();
+        assert!(delay <= Duration::from_secs(60));
+        assert!(state.dirty.load(Ordering::SeqCst));
+
+        assert_eq!(state.save_if_dirty(&states, true).unwrap(), None);
+        assert!(!state.dirty.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn window_flags_only_apply_without_explicit_flags() {
+        let state = PluginState {
+            state_flags: StateFlags::all(),
+            window_flags: HashMap::from([("main".to_string(), StateFlags::SIZE)]),
+            ..Default::default()
+        };
+        assert_eq!(state.flags_for("main", None), StateFlags::SIZE);
+        assert_eq!(state.flags_for("other", None), StateFlags::all());
+        assert_eq!(
+            state.flags_for("main", Some(StateFlags::POSITION)),
+            StateFlags::POSITION
+        );
+        assert_eq!(
+            state.flags_for("other", Some(StateFlags::POSITION)),
+            StateFlags::POSITION
+        );
+    }
+
+    #[test]
+    fn is_default_ignores_fields_excluded_by_the_flags() {
+        let state = WindowState {
+            maximized: true,
+            ..Default::default()
+        };
+        assert!(state.is_default(StateFlags::SIZE | StateFlags::POSITION));
+        assert!(!state.is_default(StateFlags::SIZE | StateFlags::MAXIMIZED));
+        assert!(!state.is_default(StateFlags::all()));
+
+        let state = WindowState {
+            prev_x: 20,
+            ..Default::default()
+        };
+        assert!(state.is_default(StateFlags::all() - StateFlags::POSITION));
+        assert!(!state.is_default(StateFlags::POSITION));
+
+        let state = WindowState {
+            scale_factor: 2.,
+            ..Default::default()
+        };
+        assert!(state.is_default(StateFlags::all()));
+    }
+
+    #[test]
+    fn rescale_scales_the_offset_on_the_monitor() {
+        let position = PhysicalPosition { x: 2020, y: 300 };
+
+        let m = monitor(1920, 0, 3840, 2160, 2.);
+        assert_eq!(
+            m.rescale(position, 1.),
+            PhysicalPosition { x: 2120, y: 600 }
+        );
+
+        let m = monitor(1920, 0, 1920, 1080, 1.);
+        assert_eq!(
+            m.rescale(position, 2.),
+            PhysicalPosition { x: 1970, y: 150 }
+        );
+    }
+
+    #[test]
+    fn rescale_keeps_positions_with_the_same_or_unknown_scale_factor() {
+        let m = monitor(0, 0, 1920, 1080, 1.5);
+        let position = PhysicalPosition { x: 100, y: 200 };
+        assert_eq!(m.rescale(position, 1.5), position);
+        assert_eq!(m.rescale(position, 0.), position);
+    }
+
+    #[test]
+    fn file_store_keeps_the_previous_state_if_a_write_is_interrupted() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(STATE_FILENAME);
+        let store = FileStore::new(&path);
+        store.save(&sample_snapshots()).unwrap();
+
+        // a save that crashed mid-write leaves a truncated temporary file beside the state file
+        let mut states = sample_states();
+        states.remove("settings");
+        let bytes = StateFormat::Bincode.serialize(&states).unwrap();
+        std::fs::write(dir.path().join(".tmpAbC123"), &bytes[..bytes.len() / 2]).unwrap();
+
+        assert_eq!(store.load().unwrap(), sample_snapshots());
+    }
+
+    #[test]
+    fn unmaximized_on_centers_a_fraction_of_the_monitor() {
+        let m = monitor(1920, 0, 3840, 2160, 2.);
+        let geometry = RestoreGeometry::unmaximized_on(m);
+        assert_eq!(
+            geometry.size,
+            LogicalSize {
+                width: 1440.,
+                height: 810.,
+            }
+        );
+        assert_eq!(geometry.position, PhysicalPosition { x: 2400, y: 270 });
     }
 }